@@ -0,0 +1,78 @@
+//! Reverse-mode autodiff: builds the backward graph for a scalar `loss` w.r.t. a
+//! set of parameter tensors, by walking the `Node` DAG in reverse topological
+//! order and accumulating gradients via each node's `Node::backward`.
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::node::topological_order;
+use crate::operators::{Add, Constant};
+use crate::tensor::{Shape, Tensor, TensorData};
+use crate::Error;
+
+/// Computes `d(loss)/d(parameter)` for each of `parameters`, accumulating with
+/// `Add` where a tensor feeds multiple consumers. A parameter whose gradient
+/// never reaches `loss` gets a zero gradient of its own shape, so the result
+/// lines up one-to-one with `parameters`.
+pub fn backward_graph(
+    loss: Arc<dyn Tensor>,
+    parameters: &[(String, Arc<dyn Tensor>)],
+) -> Result<Vec<(String, Arc<dyn Tensor>)>, Error> {
+    let mut nodes = HashSet::new();
+    loss.get_nodes(&mut nodes);
+    let order = topological_order(&nodes);
+
+    let mut grads: HashMap<*const dyn Tensor, Arc<dyn Tensor>> = HashMap::new();
+    let seed = Constant::new(None, TensorData::fill(Shape::new(vec![]), 1.0f32)?) as Arc<dyn Tensor>;
+    grads.insert(loss.as_ref() as *const dyn Tensor, seed);
+
+    for node in order.into_iter().rev() {
+        let grad_output = match node.get_output_tensors().as_slice() {
+            [single] => match grads.get(&(*single as *const dyn Tensor)) {
+                Some(grad) => grad.clone(),
+                None => continue, // this node's output never receives a gradient
+            },
+            // Multi-output nodes would need their per-output gradients combined
+            // before calling `backward`; not needed by any node type yet.
+            _ => continue,
+        };
+
+        for (input, input_grad) in node.get_input_tensors().iter().zip(node.backward(grad_output)) {
+            let Some(input_grad) = input_grad else { continue };
+            let key = *input as *const dyn Tensor;
+            match grads.remove(&key) {
+                Some(existing) => grads.insert(key, Add::new(None, existing, input_grad)? as Arc<dyn Tensor>),
+                None => grads.insert(key, input_grad),
+            };
+        }
+    }
+
+    parameters
+        .iter()
+        .map(|(name, param)| {
+            let grad = match grads.get(&(param.as_ref() as *const dyn Tensor)) {
+                Some(grad) => grad.clone(),
+                None => Constant::new(None, TensorData::fill(param.shape().clone(), 0.0f32)?) as Arc<dyn Tensor>,
+            };
+            Ok((format!("{}.grad", name), grad))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operators::Mul;
+
+    #[test]
+    fn mul_self_gradient_is_two_x() {
+        let x = Constant::new(None, TensorData::fill(Shape::new(vec![]), 3.0f32).unwrap()) as Arc<dyn Tensor>;
+        let loss = Mul::new(None, x.clone(), x.clone()).unwrap() as Arc<dyn Tensor>;
+
+        let grads = backward_graph(loss, &[("x".to_string(), x)]).unwrap();
+
+        assert_eq!(grads.len(), 1);
+        assert_eq!(grads[0].0, "x.grad");
+        let grad_data = grads[0].1.get_producing_node().unwrap().resolve_output_data().unwrap();
+        assert_eq!(grad_data.to_f64_vec(), vec![6.0]);
+    }
+}
@@ -3,6 +3,11 @@ pub mod weights;
 pub mod tensor;
 mod node;
 pub mod pytorch;
+pub mod execute;
+pub mod optimize;
+mod opset;
+pub mod validate;
+pub mod autodiff;
 
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
@@ -39,6 +44,14 @@ pub enum Error {
     InvalidDTypeError,
     #[error("Cannot resolve data")]
     CannotResolveDataError,
+    #[error("Unsupported op for GPU execution: {0}")]
+    UnsupportedOpError(String),
+    #[error("Unsupported weight storage strategy: {0}")]
+    UnsupportedWeightStorageError(String),
+    #[error("Opset {1} pinned for domain {0} is older than the {2} it requires")]
+    UnsupportedOpsetError(String, i64, i64),
+    #[error("Value mismatch at index {0}: {1} != {2}")]
+    ValueMismatchError(usize, f64, f64),
     #[error(transparent)]
     IoError(#[from] std::io::Error),
     #[error(transparent)]
@@ -59,9 +72,15 @@ impl WeightStorageStrategy {
     fn get_manager<'a>(&'a self) -> Result<Box<dyn WeightExternalOutputManager<'a> + 'a>, Error> {
         match self {
             WeightStorageStrategy::None => Ok(Box::new(weights::NullOutputManager::new())),
-            WeightStorageStrategy::BinFile(path) => Ok(Box::new(weights::BinOutputManager::<'a>::new(path))),
+            // External `.bin` weight files (offset-tracked byte writes plus the
+            // matching `TensorProto.external_data` entries) aren't implemented
+            // yet; error here rather than silently falling back to embedding,
+            // so callers who asked for external storage don't get a model that
+            // quietly ignores it.
+            WeightStorageStrategy::BinFile(_) => Err(Error::UnsupportedWeightStorageError(
+                "WeightStorageStrategy::BinFile isn't implemented yet; use ::None or ::EmbeddedData".to_string(),
+            )),
             WeightStorageStrategy::EmbeddedData => Ok(Box::new(weights::EmbeddedOutputManager::<'a>::new())),
-            _ => panic!()
         }
     }
 }
@@ -69,7 +88,8 @@ impl WeightStorageStrategy {
 pub fn build_proto(
     inputs: &[Arc<InputTensor>],
     outputs: &[(String, Arc<dyn Tensor>)],
-    weight_storage: WeightStorageStrategy
+    weight_storage: WeightStorageStrategy,
+    opset_overrides: &HashMap<String, i64>,
 ) -> Result<onnx::ModelProto, Error> {
     
     // Get all nodes in graph
@@ -89,6 +109,8 @@ pub fn build_proto(
     }
     println!("Found {} nodes in graph", nodes.len());
 
+    let opset_import = opset::resolve_opset_imports(&nodes, opset_overrides)?;
+
     // Get all tensors in graph
     let mut tensors = HashSet::new();
     for (_, tensor) in outputs {
@@ -173,7 +195,7 @@ pub fn build_proto(
     let own_version = env!("CARGO_PKG_VERSION").to_string();
     Ok(onnx::ModelProto {
         ir_version: onnx::Version::IrVersion2024325 as i64,
-        opset_import: vec![],
+        opset_import,
         producer_version: own_version,
         domain: String::new(),
         model_version: 0,
@@ -184,4 +206,42 @@ pub fn build_proto(
         functions: vec![],
         .. Default::default()
     })
+}
+
+/// Like [`build_proto`], but for exporting to an external ONNX training runtime:
+/// alongside the ordinary inference graph, populates `training_info` with an
+/// `algorithm` subgraph that computes `d(loss)/d(parameter)` for each of
+/// `parameters`, via [`autodiff::backward_graph`]. Each parameter's gradient
+/// output is named `"{parameter}.grad"` and bound back to it through
+/// `update_binding`, so the runtime knows which tensor each gradient updates.
+pub fn build_training_proto(
+    inputs: &[Arc<InputTensor>],
+    outputs: &[(String, Arc<dyn Tensor>)],
+    loss: Arc<dyn Tensor>,
+    parameters: &[(String, Arc<dyn Tensor>)],
+    weight_storage: WeightStorageStrategy,
+    opset_overrides: &HashMap<String, i64>,
+) -> Result<onnx::ModelProto, Error> {
+    let mut model = build_proto(inputs, outputs, weight_storage, opset_overrides)?;
+
+    let gradients = autodiff::backward_graph(loss, parameters)?;
+    let algorithm = build_proto(inputs, &gradients, WeightStorageStrategy::None, opset_overrides)?
+        .graph
+        .ok_or(Error::OtherError)?;
+
+    let update_binding = parameters
+        .iter()
+        .map(|(name, _)| onnx::StringStringEntryProto {
+            key: format!("{}.grad", name),
+            value: name.clone(),
+        })
+        .collect();
+
+    model.training_info = vec![onnx::TrainingInfoProto {
+        initialization: None,
+        algorithm: Some(algorithm),
+        initialization_binding: vec![],
+        update_binding,
+    }];
+    Ok(model)
 }
\ No newline at end of file
@@ -4,7 +4,8 @@ use std::default::Default;
 use std::sync::Arc;
 use crate::tensor::{Shape, TensorData};
 use crate::tensor::Tensor;
-use crate::DType;
+use crate::weights::WeightExternalOutputManager;
+use crate::{DType, Error};
 
 pub trait Node {
     fn get_input_tensors(&self) -> Vec<&dyn Tensor> {
@@ -46,6 +47,50 @@ pub trait Node {
         "ai.onnx"
     }
 
+    /// If this node's output is knowable without running the graph (e.g. a
+    /// `Constant`, or any node whose inputs all resolve), returns that data.
+    /// Lives on `Node` rather than `SingleOutputNode` so passes that only see
+    /// `&dyn Node` (as `build_proto` collects) can still fold constants.
+    fn resolve_output_data(&self) -> Option<TensorData> {
+        None
+    }
+
+    /// Reverse-mode gradient rule: given the gradient flowing back from this
+    /// node's output, produces the gradient w.r.t. each of `get_input_tensors()`,
+    /// in the same order. `None` means that input doesn't need a gradient (e.g.
+    /// a shape or axis constant). Nodes that don't override this return all
+    /// `None`, which stops backprop through them.
+    fn backward(&self, grad_output: Arc<dyn Tensor>) -> Vec<Option<Arc<dyn Tensor>>> {
+        let _ = grad_output;
+        self.get_input_tensors().iter().map(|_| None).collect()
+    }
+
+    /// Rebuilds this node with some of its inputs replaced, e.g. by a
+    /// constant-folded equivalent. `replacements` is the same length and order
+    /// as `get_input_tensors()`; `Some` at index `i` means "use this instead of
+    /// input `i`", `None` means "keep my existing input `i` unchanged" (node
+    /// types look that input back up from their own fields, since this trait
+    /// only hands out borrowed `&dyn Tensor`, not ownership).
+    ///
+    /// Returns `None` if this node type can't be rebuilt this way (e.g.
+    /// `Constant`, which has no inputs) or if none of `replacements` applies;
+    /// `optimize::optimize` leaves such nodes wired to their original inputs.
+    fn with_inputs(&self, replacements: Vec<Option<Arc<dyn Tensor>>>) -> Option<Arc<dyn Tensor>> {
+        let _ = replacements;
+        None
+    }
+
+    /// Exposes the concrete node behind `&dyn Node`, for the rare rewrite that
+    /// needs to recognize a specific node kind rather than just its
+    /// `get_onnx_type()` string, e.g. folding an `Add`'s constant operand into
+    /// a `MatMulUnary` it directly follows.
+    fn as_any(&self) -> &dyn std::any::Any
+    where
+        Self: 'static,
+    {
+        self
+    }
+
     fn get_onnx_attributes(&self) -> Vec<crate::onnx::AttributeProto>;
 
     fn to_node_proto(&self, name: Option<String>, tensor_names: &HashMap<&dyn Tensor, String>) -> crate::onnx::NodeProto {
@@ -117,6 +162,11 @@ impl Tensor for MultiOutputNodeOutput {
     fn get_sub_tensors<'a>(&'a self, table: &mut HashSet<&'a dyn Tensor>) {
         self.parent.get_tensors(table)
     }
+
+    fn get_producing_node(&self) -> Option<&dyn Node> {
+        let node: &dyn Node = self.parent.as_ref();
+        Some(node)
+    }
 }
 
 
@@ -124,8 +174,46 @@ pub(crate) trait SingleOutputNode: Node {
     fn get_output_shape(&self) -> &Shape;
 
     fn get_output_dtype(&self) -> DType;
-    
-    fn resolve_output_data(&self) -> Option<TensorData> {
-        None
+
+    /// Builds the `TensorProto` this node contributes as a `GraphProto`
+    /// initializer, if any (most ops have none; only `Constant` overrides
+    /// this). `manager` is accepted for forward compatibility with storage
+    /// strategies that need to write tensor bytes elsewhere, but every
+    /// `WeightExternalOutputManager` impl that can currently be constructed
+    /// embeds inline, so it goes unused today.
+    fn get_initializer(
+        &self,
+        name: String,
+        manager: &mut dyn WeightExternalOutputManager,
+    ) -> Result<Option<crate::onnx::TensorProto>, Error> {
+        let _ = (name, manager);
+        Ok(None)
+    }
+}
+
+/// Topologically sorts `nodes` (inputs before the nodes that consume them), for
+/// passes that need to visit a graph in execution order, forwards or backwards.
+pub(crate) fn topological_order<'a>(nodes: &HashSet<&'a dyn Node>) -> Vec<&'a dyn Node> {
+    let mut visited = HashSet::new();
+    let mut order = Vec::with_capacity(nodes.len());
+
+    // Steps to each input's single producing node rather than rebuilding that
+    // input's whole upstream closure (as `Tensor::get_nodes` would), so this is
+    // linear in the graph's nodes and edges instead of quadratic-or-worse.
+    fn visit<'a>(node: &'a dyn Node, visited: &mut HashSet<&'a dyn Node>, order: &mut Vec<&'a dyn Node>) {
+        if !visited.insert(node) {
+            return;
+        }
+        for input in node.get_input_tensors() {
+            if let Some(producer) = input.get_producing_node() {
+                visit(producer, visited, order);
+            }
+        }
+        order.push(node);
+    }
+
+    for node in nodes {
+        visit(*node, &mut visited, &mut order);
     }
+    order
 }
\ No newline at end of file
@@ -0,0 +1,1403 @@
+use std::sync::Arc;
+
+use crate::node::{Node, SingleOutputNode};
+use crate::tensor::{DType, Shape, Tensor, TensorData, TensorDataValue};
+use crate::Error;
+
+fn attribute(name: &str, value: crate::onnx::AttributeProto) -> crate::onnx::AttributeProto {
+    crate::onnx::AttributeProto { name: name.to_string(), ..value }
+}
+
+fn int_attribute(name: &str, value: i64) -> crate::onnx::AttributeProto {
+    attribute(
+        name,
+        crate::onnx::AttributeProto {
+            i: value,
+            r#type: crate::onnx::attribute_proto::AttributeType::Int as i32,
+            ..Default::default()
+        },
+    )
+}
+
+fn float_attribute(name: &str, value: f32) -> crate::onnx::AttributeProto {
+    attribute(
+        name,
+        crate::onnx::AttributeProto {
+            f: value,
+            r#type: crate::onnx::attribute_proto::AttributeType::Float as i32,
+            ..Default::default()
+        },
+    )
+}
+
+fn ints_attribute(name: &str, value: Vec<i64>) -> crate::onnx::AttributeProto {
+    attribute(
+        name,
+        crate::onnx::AttributeProto {
+            ints: value,
+            r#type: crate::onnx::attribute_proto::AttributeType::Ints as i32,
+            ..Default::default()
+        },
+    )
+}
+
+fn tensor_attribute(name: &str, value: &TensorData) -> crate::onnx::AttributeProto {
+    attribute(
+        name,
+        crate::onnx::AttributeProto {
+            t: Some(value.to_tensor_proto(name.to_string())),
+            r#type: crate::onnx::attribute_proto::AttributeType::Tensor as i32,
+            ..Default::default()
+        },
+    )
+}
+
+fn elementwise_shape(a: &Arc<dyn Tensor>, b: &Arc<dyn Tensor>) -> Result<(Shape, DType), Error> {
+    if a.dtype() != b.dtype() {
+        return Err(Error::DTypeMismatchError(a.dtype(), b.dtype()));
+    }
+    let shape = if a.rank() >= b.rank() { a.shape().clone() } else { b.shape().clone() };
+    Ok((shape, a.dtype()))
+}
+
+/// Evaluates an elementwise binary op over already-resolved operand data, for
+/// `resolve_output_data` on ops whose inputs both turn out to be constant (e.g.
+/// a `Constant + Constant` feeding a larger, non-constant op elsewhere). Only
+/// handles the non-broadcasting case (`a` and `b` with the same element count);
+/// broadcasting operands fall back to not folding rather than faking it.
+fn eval_elementwise(
+    a: &TensorData,
+    b: &TensorData,
+    output_shape: &Shape,
+    output_dtype: DType,
+    op: fn(f64, f64) -> f64,
+) -> Option<TensorData> {
+    let (av, bv) = (a.to_f64_vec(), b.to_f64_vec());
+    if av.len() != bv.len() {
+        return None;
+    }
+    let values: Vec<f64> = av.iter().zip(&bv).map(|(&x, &y)| op(x, y)).collect();
+    let data = match output_dtype {
+        DType::F32 => TensorDataValue::F32(values.iter().map(|&v| v as f32).collect()),
+        DType::F64 => TensorDataValue::F64(values),
+        DType::I32 => TensorDataValue::I32(values.iter().map(|&v| v as i32).collect()),
+        DType::I64 => TensorDataValue::I64(values.iter().map(|&v| v as i64).collect()),
+        DType::Bool | DType::F16 => return None,
+    };
+    TensorData::new(data, output_shape.clone()).ok()
+}
+
+/// Evaluates an elementwise unary op over already-resolved operand data, same
+/// caveats as [`eval_elementwise`].
+fn eval_unary(input: &TensorData, output_shape: &Shape, output_dtype: DType, op: fn(f64) -> f64) -> Option<TensorData> {
+    let values: Vec<f64> = input.to_f64_vec().iter().map(|&x| op(x)).collect();
+    let data = match output_dtype {
+        DType::F32 => TensorDataValue::F32(values.iter().map(|&v| v as f32).collect()),
+        DType::F64 => TensorDataValue::F64(values),
+        DType::I32 => TensorDataValue::I32(values.iter().map(|&v| v as i32).collect()),
+        DType::I64 => TensorDataValue::I64(values.iter().map(|&v| v as i64).collect()),
+        DType::Bool | DType::F16 => return None,
+    };
+    TensorData::new(data, output_shape.clone()).ok()
+}
+
+/// ONNX `MatMul` shape inference: the last two dims of each operand are the
+/// matrix dims (`[a_rows, k]` x `[k, b_cols]` -> `[a_rows, b_cols]`), and any
+/// leading dims are batch dims broadcast numpy-style, right-aligned, with a
+/// missing or size-1 dim on one side deferring to the other.
+fn matmul_output_shape(a: &Shape, b: &Shape) -> Result<Shape, Error> {
+    let (a_rank, b_rank) = (a.rank(), b.rank());
+    if a_rank < 2 || b_rank < 2 {
+        return Err(Error::IncompatibleShapeError(a.clone(), b.clone()));
+    }
+    let a_batch = &a.dims()[..a_rank - 2];
+    let b_batch = &b.dims()[..b_rank - 2];
+    let batch_rank = a_batch.len().max(b_batch.len());
+    let mut dims = Vec::with_capacity(batch_rank + 2);
+    for i in 0..batch_rank {
+        let from_end = batch_rank - i;
+        let a_dim = a_batch.len().checked_sub(from_end).map(|idx| &a_batch[idx]);
+        let b_dim = b_batch.len().checked_sub(from_end).map(|idx| &b_batch[idx]);
+        dims.push(match (a_dim, b_dim) {
+            (Some(a_dim), Some(b_dim)) if a_dim.value() == Some(1) => b_dim.clone(),
+            (Some(a_dim), Some(b_dim)) if b_dim.value() == Some(1) => a_dim.clone(),
+            (Some(a_dim), Some(b_dim)) if a_dim.value() != b_dim.value() => {
+                return Err(Error::IncompatibleShapeError(a.clone(), b.clone()));
+            }
+            (Some(a_dim), _) => a_dim.clone(),
+            (None, Some(b_dim)) => b_dim.clone(),
+            (None, None) => unreachable!("batch_rank is the max of both lengths"),
+        });
+    }
+    dims.push(a.dims()[a_rank - 2].clone());
+    dims.push(b.dims()[b_rank - 1].clone());
+    Ok(Shape::new(dims))
+}
+
+/// Holds a materialized value: the graph's leaves (weights, baked-in shapes and
+/// axes) are all `Constant`s.
+pub struct Constant {
+    name: Option<String>,
+    data: TensorData,
+}
+
+impl Constant {
+    pub fn new(name: Option<String>, data: TensorData) -> Arc<Self> {
+        Arc::new(Self { name, data })
+    }
+
+    pub fn data(&self) -> &TensorData {
+        &self.data
+    }
+}
+
+impl Node for Constant {
+    fn get_output_tensors(&self) -> Vec<&dyn Tensor> {
+        vec![self as &dyn Tensor]
+    }
+
+    fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn get_onnx_type(&self) -> &str {
+        "Constant"
+    }
+
+    fn get_onnx_attributes(&self) -> Vec<crate::onnx::AttributeProto> {
+        vec![tensor_attribute("value", &self.data)]
+    }
+
+    fn resolve_output_data(&self) -> Option<TensorData> {
+        Some(self.data.clone())
+    }
+}
+
+impl SingleOutputNode for Constant {
+    fn get_output_shape(&self) -> &Shape {
+        self.data.shape()
+    }
+
+    fn get_output_dtype(&self) -> DType {
+        self.data.dtype()
+    }
+
+    fn get_initializer(
+        &self,
+        name: String,
+        _manager: &mut dyn crate::weights::WeightExternalOutputManager,
+    ) -> Result<Option<crate::onnx::TensorProto>, Error> {
+        Ok(Some(self.data.to_tensor_proto(name)))
+    }
+}
+
+macro_rules! binary_op {
+    ($name:ident, $onnx_type:literal, $eval:expr, $backward:expr) => {
+        binary_op!($name, $onnx_type, $eval, $backward, |_op: &$name, _a: &Arc<dyn Tensor>, _b: &Arc<dyn Tensor>| None);
+    };
+    ($name:ident, $onnx_type:literal, $eval:expr, $backward:expr, $fuse:expr) => {
+        pub struct $name {
+            name: Option<String>,
+            a: Arc<dyn Tensor>,
+            b: Arc<dyn Tensor>,
+            output_shape: Shape,
+            output_dtype: DType,
+        }
+
+        impl $name {
+            pub fn new(name: Option<String>, a: Arc<dyn Tensor>, b: Arc<dyn Tensor>) -> Result<Arc<Self>, Error> {
+                let (output_shape, output_dtype) = elementwise_shape(&a, &b)?;
+                Ok(Arc::new(Self { name, a, b, output_shape, output_dtype }))
+            }
+        }
+
+        impl Node for $name {
+            fn get_input_tensors(&self) -> Vec<&dyn Tensor> {
+                vec![self.a.as_ref(), self.b.as_ref()]
+            }
+
+            fn get_output_tensors(&self) -> Vec<&dyn Tensor> {
+                vec![self as &dyn Tensor]
+            }
+
+            fn get_name(&self) -> Option<&str> {
+                self.name.as_deref()
+            }
+
+            fn get_onnx_type(&self) -> &str {
+                $onnx_type
+            }
+
+            fn get_onnx_attributes(&self) -> Vec<crate::onnx::AttributeProto> {
+                vec![]
+            }
+
+            fn resolve_output_data(&self) -> Option<TensorData> {
+                let a = self.a.get_producing_node()?.resolve_output_data()?;
+                let b = self.b.get_producing_node()?.resolve_output_data()?;
+                eval_elementwise(&a, &b, &self.output_shape, self.output_dtype, $eval)
+            }
+
+            fn backward(&self, grad_output: Arc<dyn Tensor>) -> Vec<Option<Arc<dyn Tensor>>> {
+                $backward(self, grad_output)
+            }
+
+            fn with_inputs(&self, replacements: Vec<Option<Arc<dyn Tensor>>>) -> Option<Arc<dyn Tensor>> {
+                let [a, b]: [Option<Arc<dyn Tensor>>; 2] = replacements.try_into().ok()?;
+                if a.is_none() && b.is_none() {
+                    return None;
+                }
+                let a = a.unwrap_or_else(|| self.a.clone());
+                let b = b.unwrap_or_else(|| self.b.clone());
+                if let Some(fused) = $fuse(self, &a, &b) {
+                    return Some(fused);
+                }
+                Some($name::new(self.name.clone(), a, b).expect("optimize only substitutes same-shape/dtype tensors") as Arc<dyn Tensor>)
+            }
+        }
+
+        impl SingleOutputNode for $name {
+            fn get_output_shape(&self) -> &Shape {
+                &self.output_shape
+            }
+
+            fn get_output_dtype(&self) -> DType {
+                self.output_dtype
+            }
+        }
+    };
+}
+
+/// `Add(MatMulUnary{bias: None}, other)` -> `MatMulUnary{bias: Some(other)}`,
+/// folding a linear layer's bias into the already-fused matmul the same way
+/// `fuse_matmul_unary` folds the weight, instead of leaving it as a separate
+/// `Add` on top of a `MatMulUnary` that can never pick it up later. `other` is
+/// taken as the bias whether or not it is constant. Tried on
+/// both operand orders since `MatMul::with_inputs` fuses eagerly, so the
+/// `MatMulUnary` this looks for is always already built by the time `Add`'s
+/// own rewrite runs.
+fn fuse_add_into_matmul_unary_bias(_op: &Add, a: &Arc<dyn Tensor>, b: &Arc<dyn Tensor>) -> Option<Arc<dyn Tensor>> {
+    for (matmul_operand, bias_operand) in [(a, b), (b, a)] {
+        let Some(matmul_unary) = matmul_operand.get_producing_node().and_then(|node| node.as_any().downcast_ref::<MatMulUnary>()) else {
+            continue;
+        };
+        if matmul_unary.bias.is_some() {
+            continue;
+        }
+        if let Ok(fused) = MatMulUnary::new(matmul_unary.name.clone(), matmul_unary.a.clone(), matmul_unary.b.clone(), Some(bias_operand.clone())) {
+            return Some(fused as Arc<dyn Tensor>);
+        }
+    }
+    None
+}
+
+// Gradient rules assume `a` and `b` are the same shape (no broadcasting), same
+// caveat as `eval_elementwise`: broadcasting operands would need the
+// broadcast-out axes summed back down, which no node type here needs yet.
+binary_op!(Add, "Add", |x: f64, y: f64| x + y, |_node: &Add, grad_output: Arc<dyn Tensor>| {
+    vec![Some(grad_output.clone()), Some(grad_output)]
+}, fuse_add_into_matmul_unary_bias);
+binary_op!(Mul, "Mul", |x: f64, y: f64| x * y, |node: &Mul, grad_output: Arc<dyn Tensor>| {
+    let da = Mul::new(None, grad_output.clone(), node.b.clone()).expect("gradient shape matches forward operands");
+    let db = Mul::new(None, grad_output, node.a.clone()).expect("gradient shape matches forward operands");
+    vec![Some(da as Arc<dyn Tensor>), Some(db as Arc<dyn Tensor>)]
+});
+binary_op!(Div, "Div", |x: f64, y: f64| x / y, |node: &Div, grad_output: Arc<dyn Tensor>| {
+    let da = Div::new(None, grad_output.clone(), node.b.clone()).expect("gradient shape matches forward operands");
+    let b_squared = Mul::new(None, node.b.clone(), node.b.clone()).expect("gradient shape matches forward operands");
+    let da_times_b = Mul::new(None, grad_output, node.a.clone()).expect("gradient shape matches forward operands");
+    let db = Neg::new(None, Div::new(None, da_times_b, b_squared).expect("gradient shape matches forward operands"));
+    vec![Some(da as Arc<dyn Tensor>), Some(db as Arc<dyn Tensor>)]
+});
+binary_op!(Sub, "Sub", |x: f64, y: f64| x - y, |_node: &Sub, grad_output: Arc<dyn Tensor>| {
+    vec![Some(grad_output.clone()), Some(Neg::new(None, grad_output) as Arc<dyn Tensor>)]
+});
+
+macro_rules! unary_op {
+    ($name:ident, $onnx_type:literal, $eval:expr) => {
+        unary_op!($name, $onnx_type, $eval, |_node: &$name, _grad_output: Arc<dyn Tensor>| vec![None]);
+    };
+    ($name:ident, $onnx_type:literal, $eval:expr, $backward:expr) => {
+        pub struct $name {
+            name: Option<String>,
+            input: Arc<dyn Tensor>,
+        }
+
+        impl $name {
+            pub fn new(name: Option<String>, input: Arc<dyn Tensor>) -> Arc<Self> {
+                Arc::new(Self { name, input })
+            }
+        }
+
+        impl Node for $name {
+            fn get_input_tensors(&self) -> Vec<&dyn Tensor> {
+                vec![self.input.as_ref()]
+            }
+
+            fn get_output_tensors(&self) -> Vec<&dyn Tensor> {
+                vec![self as &dyn Tensor]
+            }
+
+            fn get_name(&self) -> Option<&str> {
+                self.name.as_deref()
+            }
+
+            fn get_onnx_type(&self) -> &str {
+                $onnx_type
+            }
+
+            fn get_onnx_attributes(&self) -> Vec<crate::onnx::AttributeProto> {
+                vec![]
+            }
+
+            fn resolve_output_data(&self) -> Option<TensorData> {
+                let input = self.input.get_producing_node()?.resolve_output_data()?;
+                eval_unary(&input, self.input.shape(), self.input.dtype(), $eval)
+            }
+
+            fn backward(&self, grad_output: Arc<dyn Tensor>) -> Vec<Option<Arc<dyn Tensor>>> {
+                $backward(self, grad_output)
+            }
+
+            fn with_inputs(&self, replacements: Vec<Option<Arc<dyn Tensor>>>) -> Option<Arc<dyn Tensor>> {
+                let [input]: [Option<Arc<dyn Tensor>>; 1] = replacements.try_into().ok()?;
+                let input = input?;
+                Some($name::new(self.name.clone(), input) as Arc<dyn Tensor>)
+            }
+        }
+
+        impl SingleOutputNode for $name {
+            fn get_output_shape(&self) -> &Shape {
+                self.input.shape()
+            }
+
+            fn get_output_dtype(&self) -> DType {
+                self.input.dtype()
+            }
+        }
+    };
+}
+
+unary_op!(Exp, "Exp", |x: f64| x.exp());
+unary_op!(Neg, "Neg", |x: f64| -x);
+// d/dx sigmoid(x) = sigmoid(x) * (1 - sigmoid(x)); `1 - sigmoid(x) == sigmoid(-x)`,
+// so this reuses Sigmoid/Neg/Mul rather than synthesizing a constant `1`.
+unary_op!(Sigmoid, "Sigmoid", |x: f64| 1.0 / (1.0 + (-x).exp()), |node: &Sigmoid, grad_output: Arc<dyn Tensor>| {
+    let y = Sigmoid::new(None, node.input.clone()) as Arc<dyn Tensor>;
+    let one_minus_y = Sigmoid::new(None, Neg::new(None, node.input.clone()) as Arc<dyn Tensor>) as Arc<dyn Tensor>;
+    let dy = Mul::new(None, grad_output, y).expect("gradient shape matches forward operand");
+    let dx = Mul::new(None, dy, one_minus_y).expect("gradient shape matches forward operand");
+    vec![Some(dx as Arc<dyn Tensor>)]
+});
+
+pub struct Cast {
+    name: Option<String>,
+    input: Arc<dyn Tensor>,
+    dtype: DType,
+}
+
+impl Cast {
+    pub fn new(name: Option<String>, input: Arc<dyn Tensor>, dtype: DType) -> Arc<Self> {
+        Arc::new(Self { name, input, dtype })
+    }
+}
+
+impl Node for Cast {
+    fn get_input_tensors(&self) -> Vec<&dyn Tensor> {
+        vec![self.input.as_ref()]
+    }
+
+    fn get_output_tensors(&self) -> Vec<&dyn Tensor> {
+        vec![self as &dyn Tensor]
+    }
+
+    fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn get_onnx_type(&self) -> &str {
+        "Cast"
+    }
+
+    fn get_onnx_attributes(&self) -> Vec<crate::onnx::AttributeProto> {
+        let onnx_dtype = match self.dtype {
+            DType::F32 => crate::onnx::tensor_proto::DataType::Float,
+            DType::F64 => crate::onnx::tensor_proto::DataType::Double,
+            DType::I32 => crate::onnx::tensor_proto::DataType::Int32,
+            DType::I64 => crate::onnx::tensor_proto::DataType::Int64,
+            DType::F16 => crate::onnx::tensor_proto::DataType::Float16,
+            DType::Bool => crate::onnx::tensor_proto::DataType::Bool,
+        };
+        vec![int_attribute("to", onnx_dtype as i64)]
+    }
+
+    fn with_inputs(&self, replacements: Vec<Option<Arc<dyn Tensor>>>) -> Option<Arc<dyn Tensor>> {
+        let [input]: [Option<Arc<dyn Tensor>>; 1] = replacements.try_into().ok()?;
+        Some(Cast::new(self.name.clone(), input?, self.dtype) as Arc<dyn Tensor>)
+    }
+}
+
+impl SingleOutputNode for Cast {
+    fn get_output_shape(&self) -> &Shape {
+        self.input.shape()
+    }
+
+    fn get_output_dtype(&self) -> DType {
+        self.dtype
+    }
+}
+
+pub struct Transpose {
+    name: Option<String>,
+    input: Arc<dyn Tensor>,
+    perm: Option<Vec<i64>>,
+    output_shape: Shape,
+}
+
+impl Transpose {
+    pub fn new(name: Option<String>, input: Arc<dyn Tensor>, perm: Option<Vec<i64>>) -> Arc<Self> {
+        let dims = input.shape().dims();
+        let order: Vec<usize> = match &perm {
+            Some(perm) => perm.iter().map(|&i| i as usize).collect(),
+            None => (0..dims.len()).rev().collect(),
+        };
+        let output_shape = Shape::new(order.iter().map(|&i| dims[i].clone()).collect());
+        Arc::new(Self { name, input, perm, output_shape })
+    }
+}
+
+impl Node for Transpose {
+    fn get_input_tensors(&self) -> Vec<&dyn Tensor> {
+        vec![self.input.as_ref()]
+    }
+
+    fn get_output_tensors(&self) -> Vec<&dyn Tensor> {
+        vec![self as &dyn Tensor]
+    }
+
+    fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn get_onnx_type(&self) -> &str {
+        "Transpose"
+    }
+
+    fn get_onnx_attributes(&self) -> Vec<crate::onnx::AttributeProto> {
+        match &self.perm {
+            Some(perm) => vec![ints_attribute("perm", perm.clone())],
+            None => vec![],
+        }
+    }
+
+    fn with_inputs(&self, replacements: Vec<Option<Arc<dyn Tensor>>>) -> Option<Arc<dyn Tensor>> {
+        let [input]: [Option<Arc<dyn Tensor>>; 1] = replacements.try_into().ok()?;
+        Some(Transpose::new(self.name.clone(), input?, self.perm.clone()) as Arc<dyn Tensor>)
+    }
+}
+
+impl SingleOutputNode for Transpose {
+    fn get_output_shape(&self) -> &Shape {
+        &self.output_shape
+    }
+
+    fn get_output_dtype(&self) -> DType {
+        self.input.dtype()
+    }
+}
+
+/// Reads the constant data backing `tensor`, if its producing node resolves
+/// statically (e.g. it's a `Constant`, or folds to one). Used by ops whose
+/// output shape is carried as a second, shape/axes-valued input rather than an
+/// attribute.
+fn resolve_i64_vec(tensor: &Arc<dyn Tensor>) -> Option<Vec<i64>> {
+    let data = tensor.get_producing_node().and_then(|node| node.resolve_output_data())?;
+    Some(data.to_f64_vec().into_iter().map(|v| v as i64).collect())
+}
+
+pub struct Reshape {
+    name: Option<String>,
+    input: Arc<dyn Tensor>,
+    shape: Arc<dyn Tensor>,
+    output_shape: Shape,
+}
+
+impl Reshape {
+    pub fn new(name: Option<String>, input: Arc<dyn Tensor>, shape: Arc<dyn Tensor>) -> Result<Arc<Self>, Error> {
+        let output_shape = match resolve_i64_vec(&shape) {
+            Some(dims) => Shape::new(dims.into_iter().map(|d| dimension_from_reshape(d)).collect()),
+            None => Shape::new(vec![]),
+        };
+        Ok(Arc::new(Self { name, input, shape, output_shape }))
+    }
+}
+
+fn dimension_from_reshape(value: i64) -> crate::tensor::Dimension {
+    if value < 0 {
+        crate::tensor::Dimension::new(None, None, None)
+    } else {
+        crate::tensor::Dimension::new(Some(value), None, None)
+    }
+}
+
+impl Node for Reshape {
+    fn get_input_tensors(&self) -> Vec<&dyn Tensor> {
+        vec![self.input.as_ref(), self.shape.as_ref()]
+    }
+
+    fn get_output_tensors(&self) -> Vec<&dyn Tensor> {
+        vec![self as &dyn Tensor]
+    }
+
+    fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn get_onnx_type(&self) -> &str {
+        "Reshape"
+    }
+
+    fn get_onnx_attributes(&self) -> Vec<crate::onnx::AttributeProto> {
+        vec![]
+    }
+
+    fn with_inputs(&self, replacements: Vec<Option<Arc<dyn Tensor>>>) -> Option<Arc<dyn Tensor>> {
+        let [input, shape]: [Option<Arc<dyn Tensor>>; 2] = replacements.try_into().ok()?;
+        if input.is_none() && shape.is_none() {
+            return None;
+        }
+        let input = input.unwrap_or_else(|| self.input.clone());
+        let shape = shape.unwrap_or_else(|| self.shape.clone());
+        Some(Reshape::new(self.name.clone(), input, shape).expect("optimize only substitutes same-shape/dtype tensors") as Arc<dyn Tensor>)
+    }
+}
+
+impl SingleOutputNode for Reshape {
+    fn get_output_shape(&self) -> &Shape {
+        &self.output_shape
+    }
+
+    fn get_output_dtype(&self) -> DType {
+        self.input.dtype()
+    }
+}
+
+macro_rules! axis_insert_remove_op {
+    ($name:ident, $onnx_type:literal, $adjust:expr) => {
+        pub struct $name {
+            name: Option<String>,
+            input: Arc<dyn Tensor>,
+            axes: Arc<dyn Tensor>,
+            output_shape: Shape,
+        }
+
+        impl $name {
+            pub fn new(name: Option<String>, input: Arc<dyn Tensor>, axes: Arc<dyn Tensor>) -> Result<Arc<Self>, Error> {
+                let mut dims = input.shape().dims().to_vec();
+                if let Some(resolved_axes) = resolve_i64_vec(&axes) {
+                    $adjust(&mut dims, &resolved_axes);
+                }
+                let output_shape = Shape::new(dims);
+                Ok(Arc::new(Self { name, input, axes, output_shape }))
+            }
+        }
+
+        impl Node for $name {
+            fn get_input_tensors(&self) -> Vec<&dyn Tensor> {
+                vec![self.input.as_ref(), self.axes.as_ref()]
+            }
+
+            fn get_output_tensors(&self) -> Vec<&dyn Tensor> {
+                vec![self as &dyn Tensor]
+            }
+
+            fn get_name(&self) -> Option<&str> {
+                self.name.as_deref()
+            }
+
+            fn get_onnx_type(&self) -> &str {
+                $onnx_type
+            }
+
+            fn get_onnx_attributes(&self) -> Vec<crate::onnx::AttributeProto> {
+                vec![]
+            }
+
+            fn with_inputs(&self, replacements: Vec<Option<Arc<dyn Tensor>>>) -> Option<Arc<dyn Tensor>> {
+                let [input, axes]: [Option<Arc<dyn Tensor>>; 2] = replacements.try_into().ok()?;
+                if input.is_none() && axes.is_none() {
+                    return None;
+                }
+                let input = input.unwrap_or_else(|| self.input.clone());
+                let axes = axes.unwrap_or_else(|| self.axes.clone());
+                Some($name::new(self.name.clone(), input, axes).expect("optimize only substitutes same-shape/dtype tensors") as Arc<dyn Tensor>)
+            }
+        }
+
+        impl SingleOutputNode for $name {
+            fn get_output_shape(&self) -> &Shape {
+                &self.output_shape
+            }
+
+            fn get_output_dtype(&self) -> DType {
+                self.input.dtype()
+            }
+        }
+    };
+}
+
+axis_insert_remove_op!(Unsqueeze, "Unsqueeze", |dims: &mut Vec<crate::tensor::Dimension>, axes: &Vec<i64>| {
+    for &axis in axes {
+        let idx = (axis.max(0) as usize).min(dims.len());
+        dims.insert(idx, crate::tensor::Dimension::new(Some(1), None, None));
+    }
+});
+
+axis_insert_remove_op!(Squeeze, "Squeeze", |dims: &mut Vec<crate::tensor::Dimension>, axes: &Vec<i64>| {
+    let mut sorted = axes.clone();
+    sorted.sort_unstable_by(|a, b| b.cmp(a));
+    for axis in sorted {
+        let idx = axis.max(0) as usize;
+        if idx < dims.len() {
+            dims.remove(idx);
+        }
+    }
+});
+
+pub struct Slice {
+    name: Option<String>,
+    input: Arc<dyn Tensor>,
+    start: Arc<dyn Tensor>,
+    end: Arc<dyn Tensor>,
+    axes: Option<Arc<dyn Tensor>>,
+    steps: Option<Arc<dyn Tensor>>,
+}
+
+impl Slice {
+    pub fn new(
+        name: Option<String>,
+        input: Arc<dyn Tensor>,
+        start: Arc<dyn Tensor>,
+        end: Arc<dyn Tensor>,
+        axes: Option<Arc<dyn Tensor>>,
+        steps: Option<Arc<dyn Tensor>>,
+    ) -> Result<Arc<Self>, Error> {
+        Ok(Arc::new(Self { name, input, start, end, axes, steps }))
+    }
+}
+
+impl Node for Slice {
+    fn get_input_tensors(&self) -> Vec<&dyn Tensor> {
+        let mut inputs = vec![self.input.as_ref(), self.start.as_ref(), self.end.as_ref()];
+        if let Some(axes) = &self.axes {
+            inputs.push(axes.as_ref());
+        }
+        if let Some(steps) = &self.steps {
+            inputs.push(steps.as_ref());
+        }
+        inputs
+    }
+
+    fn get_output_tensors(&self) -> Vec<&dyn Tensor> {
+        vec![self as &dyn Tensor]
+    }
+
+    fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn get_onnx_type(&self) -> &str {
+        "Slice"
+    }
+
+    fn get_onnx_attributes(&self) -> Vec<crate::onnx::AttributeProto> {
+        vec![]
+    }
+
+    fn with_inputs(&self, replacements: Vec<Option<Arc<dyn Tensor>>>) -> Option<Arc<dyn Tensor>> {
+        let mut replacements = replacements.into_iter();
+        let input = replacements.next()?;
+        let start = replacements.next()?;
+        let end = replacements.next()?;
+        let axes = if self.axes.is_some() { Some(replacements.next()?) } else { None };
+        let steps = if self.steps.is_some() { Some(replacements.next()?) } else { None };
+        if replacements.next().is_some() {
+            return None;
+        }
+        let any_changed = input.is_some()
+            || start.is_some()
+            || end.is_some()
+            || axes.as_ref().is_some_and(Option::is_some)
+            || steps.as_ref().is_some_and(Option::is_some);
+        if !any_changed {
+            return None;
+        }
+        let input = input.unwrap_or_else(|| self.input.clone());
+        let start = start.unwrap_or_else(|| self.start.clone());
+        let end = end.unwrap_or_else(|| self.end.clone());
+        let axes = axes.flatten().or_else(|| self.axes.clone());
+        let steps = steps.flatten().or_else(|| self.steps.clone());
+        Some(
+            Slice::new(self.name.clone(), input, start, end, axes, steps)
+                .expect("optimize only substitutes same-shape/dtype tensors") as Arc<dyn Tensor>,
+        )
+    }
+}
+
+impl SingleOutputNode for Slice {
+    fn get_output_shape(&self) -> &Shape {
+        // Slicing a statically-unresolvable range keeps the input's rank; the
+        // exact per-axis extents aren't knowable without the constant data.
+        self.input.shape()
+    }
+
+    fn get_output_dtype(&self) -> DType {
+        self.input.dtype()
+    }
+}
+
+pub struct Expand {
+    name: Option<String>,
+    input: Arc<dyn Tensor>,
+    shape: Arc<dyn Tensor>,
+    output_shape: Shape,
+}
+
+impl Expand {
+    pub fn new(name: Option<String>, input: Arc<dyn Tensor>, shape: Arc<dyn Tensor>) -> Result<Arc<Self>, Error> {
+        let output_shape = match resolve_i64_vec(&shape) {
+            Some(dims) => Shape::new(dims.into_iter().map(dimension_from_reshape).collect()),
+            None => input.shape().clone(),
+        };
+        Ok(Arc::new(Self { name, input, shape, output_shape }))
+    }
+}
+
+impl Node for Expand {
+    fn get_input_tensors(&self) -> Vec<&dyn Tensor> {
+        vec![self.input.as_ref(), self.shape.as_ref()]
+    }
+
+    fn get_output_tensors(&self) -> Vec<&dyn Tensor> {
+        vec![self as &dyn Tensor]
+    }
+
+    fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn get_onnx_type(&self) -> &str {
+        "Expand"
+    }
+
+    fn get_onnx_attributes(&self) -> Vec<crate::onnx::AttributeProto> {
+        vec![]
+    }
+
+    fn with_inputs(&self, replacements: Vec<Option<Arc<dyn Tensor>>>) -> Option<Arc<dyn Tensor>> {
+        let [input, shape]: [Option<Arc<dyn Tensor>>; 2] = replacements.try_into().ok()?;
+        if input.is_none() && shape.is_none() {
+            return None;
+        }
+        let input = input.unwrap_or_else(|| self.input.clone());
+        let shape = shape.unwrap_or_else(|| self.shape.clone());
+        Some(Expand::new(self.name.clone(), input, shape).expect("optimize only substitutes same-shape/dtype tensors") as Arc<dyn Tensor>)
+    }
+}
+
+impl SingleOutputNode for Expand {
+    fn get_output_shape(&self) -> &Shape {
+        &self.output_shape
+    }
+
+    fn get_output_dtype(&self) -> DType {
+        self.input.dtype()
+    }
+}
+
+pub struct CumSum {
+    name: Option<String>,
+    input: Arc<dyn Tensor>,
+    axis: Arc<dyn Tensor>,
+}
+
+impl CumSum {
+    pub fn new(name: Option<String>, input: Arc<dyn Tensor>, axis: Arc<dyn Tensor>) -> Result<Arc<Self>, Error> {
+        Ok(Arc::new(Self { name, input, axis }))
+    }
+}
+
+impl Node for CumSum {
+    fn get_input_tensors(&self) -> Vec<&dyn Tensor> {
+        vec![self.input.as_ref(), self.axis.as_ref()]
+    }
+
+    fn get_output_tensors(&self) -> Vec<&dyn Tensor> {
+        vec![self as &dyn Tensor]
+    }
+
+    fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn get_onnx_type(&self) -> &str {
+        "CumSum"
+    }
+
+    fn get_onnx_attributes(&self) -> Vec<crate::onnx::AttributeProto> {
+        vec![]
+    }
+
+    fn with_inputs(&self, replacements: Vec<Option<Arc<dyn Tensor>>>) -> Option<Arc<dyn Tensor>> {
+        let [input, axis]: [Option<Arc<dyn Tensor>>; 2] = replacements.try_into().ok()?;
+        if input.is_none() && axis.is_none() {
+            return None;
+        }
+        let input = input.unwrap_or_else(|| self.input.clone());
+        let axis = axis.unwrap_or_else(|| self.axis.clone());
+        Some(CumSum::new(self.name.clone(), input, axis).expect("optimize only substitutes same-shape/dtype tensors") as Arc<dyn Tensor>)
+    }
+}
+
+impl SingleOutputNode for CumSum {
+    fn get_output_shape(&self) -> &Shape {
+        self.input.shape()
+    }
+
+    fn get_output_dtype(&self) -> DType {
+        self.input.dtype()
+    }
+}
+
+macro_rules! reduce_op {
+    ($name:ident, $onnx_type:literal) => {
+        pub struct $name {
+            name: Option<String>,
+            input: Arc<dyn Tensor>,
+            axes: Arc<dyn Tensor>,
+            keepdims: i64,
+            output_shape: Shape,
+        }
+
+        impl $name {
+            pub fn new(
+                name: Option<String>,
+                input: Arc<dyn Tensor>,
+                axes: Arc<dyn Tensor>,
+                keepdims: i64,
+            ) -> Result<Arc<Self>, Error> {
+                let mut dims = input.shape().dims().to_vec();
+                if let Some(resolved_axes) = resolve_i64_vec(&axes) {
+                    for axis in resolved_axes {
+                        let idx = axis.max(0) as usize;
+                        if idx >= dims.len() {
+                            continue;
+                        }
+                        if keepdims != 0 {
+                            dims[idx] = crate::tensor::Dimension::new(Some(1), None, None);
+                        } else {
+                            dims.remove(idx);
+                        }
+                    }
+                }
+                let output_shape = Shape::new(dims);
+                Ok(Arc::new(Self { name, input, axes, keepdims, output_shape }))
+            }
+        }
+
+        impl Node for $name {
+            fn get_input_tensors(&self) -> Vec<&dyn Tensor> {
+                vec![self.input.as_ref(), self.axes.as_ref()]
+            }
+
+            fn get_output_tensors(&self) -> Vec<&dyn Tensor> {
+                vec![self as &dyn Tensor]
+            }
+
+            fn get_name(&self) -> Option<&str> {
+                self.name.as_deref()
+            }
+
+            fn get_onnx_type(&self) -> &str {
+                $onnx_type
+            }
+
+            fn get_onnx_attributes(&self) -> Vec<crate::onnx::AttributeProto> {
+                vec![int_attribute("keepdims", self.keepdims)]
+            }
+
+            fn with_inputs(&self, replacements: Vec<Option<Arc<dyn Tensor>>>) -> Option<Arc<dyn Tensor>> {
+                let [input, axes]: [Option<Arc<dyn Tensor>>; 2] = replacements.try_into().ok()?;
+                if input.is_none() && axes.is_none() {
+                    return None;
+                }
+                let input = input.unwrap_or_else(|| self.input.clone());
+                let axes = axes.unwrap_or_else(|| self.axes.clone());
+                Some(
+                    $name::new(self.name.clone(), input, axes, self.keepdims)
+                        .expect("optimize only substitutes same-shape/dtype tensors") as Arc<dyn Tensor>,
+                )
+            }
+        }
+
+        impl SingleOutputNode for $name {
+            fn get_output_shape(&self) -> &Shape {
+                &self.output_shape
+            }
+
+            fn get_output_dtype(&self) -> DType {
+                self.input.dtype()
+            }
+        }
+    };
+}
+
+reduce_op!(ReduceMax, "ReduceMax");
+reduce_op!(ReduceSum, "ReduceSum");
+
+pub struct MatMul {
+    name: Option<String>,
+    a: Arc<dyn Tensor>,
+    b: Arc<dyn Tensor>,
+    output_shape: Shape,
+    output_dtype: DType,
+}
+
+impl MatMul {
+    pub fn new(name: Option<String>, a: Arc<dyn Tensor>, b: Arc<dyn Tensor>) -> Result<Arc<Self>, Error> {
+        if a.dtype() != b.dtype() {
+            return Err(Error::DTypeMismatchError(a.dtype(), b.dtype()));
+        }
+        let output_shape = matmul_output_shape(a.shape(), b.shape())?;
+        let output_dtype = a.dtype();
+        Ok(Arc::new(Self { name, a, b, output_shape, output_dtype }))
+    }
+}
+
+impl Node for MatMul {
+    fn get_input_tensors(&self) -> Vec<&dyn Tensor> {
+        vec![self.a.as_ref(), self.b.as_ref()]
+    }
+
+    fn get_output_tensors(&self) -> Vec<&dyn Tensor> {
+        vec![self as &dyn Tensor]
+    }
+
+    fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn get_onnx_type(&self) -> &str {
+        "MatMul"
+    }
+
+    fn get_onnx_attributes(&self) -> Vec<crate::onnx::AttributeProto> {
+        vec![]
+    }
+
+    fn backward(&self, grad_output: Arc<dyn Tensor>) -> Vec<Option<Arc<dyn Tensor>>> {
+        // d(a @ b)/da = grad_output @ b^T, d(a @ b)/db = a^T @ grad_output.
+        let da = MatMul::new(None, grad_output.clone(), transpose_last_two(self.b.clone()))
+            .expect("gradient shape matches forward operands");
+        let db = MatMul::new(None, transpose_last_two(self.a.clone()), grad_output)
+            .expect("gradient shape matches forward operands");
+        vec![Some(da as Arc<dyn Tensor>), Some(db as Arc<dyn Tensor>)]
+    }
+
+    // `optimize`'s MatMul-unary fusion lives here rather than as a separate
+    // rewrite step: this is the one place that already has owned `a`/`b`
+    // (substituted or `self`'s own), which a generic pass can't produce (see
+    // `Node::with_inputs`'s doc comment).
+    fn with_inputs(&self, replacements: Vec<Option<Arc<dyn Tensor>>>) -> Option<Arc<dyn Tensor>> {
+        let [a, b]: [Option<Arc<dyn Tensor>>; 2] = replacements.try_into().ok()?;
+        if a.is_none() && b.is_none() {
+            return None;
+        }
+        let a = a.unwrap_or_else(|| self.a.clone());
+        let b = b.unwrap_or_else(|| self.b.clone());
+        Some(
+            crate::optimize::fuse_matmul_unary(self.name.clone(), a, b, None)
+                .expect("optimize only substitutes same-shape/dtype tensors"),
+        )
+    }
+}
+
+impl SingleOutputNode for MatMul {
+    fn get_output_shape(&self) -> &Shape {
+        &self.output_shape
+    }
+
+    fn get_output_dtype(&self) -> DType {
+        self.output_dtype
+    }
+}
+
+/// Swaps a tensor's last two axes, e.g. for building `a^T`/`b^T` in `MatMul`'s
+/// gradient rule. `MatMul::new` requires both operands be at least rank 2, so
+/// there's always a last-two pair to swap.
+fn transpose_last_two(input: Arc<dyn Tensor>) -> Arc<dyn Tensor> {
+    let rank = input.rank();
+    let mut perm: Vec<i64> = (0..rank as i64).collect();
+    perm.swap(rank - 2, rank - 1);
+    Transpose::new(None, input, Some(perm)) as Arc<dyn Tensor>
+}
+
+/// Tract's `MatMulUnary`: a `MatMul` whose `a` operand is baked in as a
+/// constant (the common case out of `pytorch::linear`, where `a` is the loaded
+/// weight), plus an optional fused bias `Add`. Not a real ONNX op, so it's
+/// emitted in the `ai.onnx.contrib` domain rather than `ai.onnx`, the way
+/// runtime-specific fused kernels usually are.
+pub struct MatMulUnary {
+    name: Option<String>,
+    a: TensorData,
+    b: Arc<dyn Tensor>,
+    bias: Option<Arc<dyn Tensor>>,
+    output_shape: Shape,
+    output_dtype: DType,
+}
+
+impl MatMulUnary {
+    pub fn new(
+        name: Option<String>,
+        a: TensorData,
+        b: Arc<dyn Tensor>,
+        bias: Option<Arc<dyn Tensor>>,
+    ) -> Result<Arc<Self>, Error> {
+        if a.dtype() != b.dtype() {
+            return Err(Error::DTypeMismatchError(a.dtype(), b.dtype()));
+        }
+        if let Some(bias) = &bias {
+            if bias.dtype() != a.dtype() {
+                return Err(Error::DTypeMismatchError(bias.dtype(), a.dtype()));
+            }
+        }
+        let output_shape = matmul_output_shape(a.shape(), b.shape())?;
+        let output_dtype = a.dtype();
+        Ok(Arc::new(Self { name, a, b, bias, output_shape, output_dtype }))
+    }
+}
+
+impl Node for MatMulUnary {
+    fn get_input_tensors(&self) -> Vec<&dyn Tensor> {
+        let mut inputs = vec![self.b.as_ref()];
+        if let Some(bias) = &self.bias {
+            inputs.push(bias.as_ref());
+        }
+        inputs
+    }
+
+    fn get_output_tensors(&self) -> Vec<&dyn Tensor> {
+        vec![self as &dyn Tensor]
+    }
+
+    fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn get_onnx_type(&self) -> &str {
+        "MatMulUnary"
+    }
+
+    fn get_onnx_domain(&self) -> &str {
+        "ai.onnx.contrib"
+    }
+
+    fn get_onnx_attributes(&self) -> Vec<crate::onnx::AttributeProto> {
+        vec![tensor_attribute("a", &self.a)]
+    }
+
+    fn with_inputs(&self, replacements: Vec<Option<Arc<dyn Tensor>>>) -> Option<Arc<dyn Tensor>> {
+        let mut replacements = replacements.into_iter();
+        let b = replacements.next()?;
+        let bias = if self.bias.is_some() { Some(replacements.next()?) } else { None };
+        if replacements.next().is_some() {
+            return None;
+        }
+        if b.is_none() && bias.as_ref().is_none_or(Option::is_none) {
+            return None;
+        }
+        let b = b.unwrap_or_else(|| self.b.clone());
+        let bias = bias.flatten().or_else(|| self.bias.clone());
+        Some(
+            MatMulUnary::new(self.name.clone(), self.a.clone(), b, bias)
+                .expect("optimize only substitutes same-shape/dtype tensors") as Arc<dyn Tensor>,
+        )
+    }
+}
+
+impl SingleOutputNode for MatMulUnary {
+    fn get_output_shape(&self) -> &Shape {
+        &self.output_shape
+    }
+
+    fn get_output_dtype(&self) -> DType {
+        self.output_dtype
+    }
+}
+
+pub struct LayerNormalization {
+    name: Option<String>,
+    input: Arc<dyn Tensor>,
+    scale: Arc<dyn Tensor>,
+    bias: Option<Arc<dyn Tensor>>,
+    axis: i64,
+    epsilon: f32,
+    stash_type: i64,
+}
+
+impl LayerNormalization {
+    pub fn new(
+        name: Option<String>,
+        input: Arc<dyn Tensor>,
+        scale: Arc<dyn Tensor>,
+        bias: Option<Arc<dyn Tensor>>,
+        axis: i64,
+        epsilon: f32,
+        stash_type: i64,
+    ) -> Result<Arc<Self>, Error> {
+        Ok(Arc::new(Self { name, input, scale, bias, axis, epsilon, stash_type }))
+    }
+}
+
+impl Node for LayerNormalization {
+    fn get_input_tensors(&self) -> Vec<&dyn Tensor> {
+        let mut inputs = vec![self.input.as_ref(), self.scale.as_ref()];
+        if let Some(bias) = &self.bias {
+            inputs.push(bias.as_ref());
+        }
+        inputs
+    }
+
+    fn get_output_tensors(&self) -> Vec<&dyn Tensor> {
+        vec![self as &dyn Tensor]
+    }
+
+    fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn get_onnx_type(&self) -> &str {
+        "LayerNormalization"
+    }
+
+    fn get_onnx_attributes(&self) -> Vec<crate::onnx::AttributeProto> {
+        vec![
+            int_attribute("axis", self.axis),
+            float_attribute("epsilon", self.epsilon),
+            int_attribute("stash_type", self.stash_type),
+        ]
+    }
+
+    fn with_inputs(&self, replacements: Vec<Option<Arc<dyn Tensor>>>) -> Option<Arc<dyn Tensor>> {
+        let mut replacements = replacements.into_iter();
+        let input = replacements.next()?;
+        let scale = replacements.next()?;
+        let bias = if self.bias.is_some() { Some(replacements.next()?) } else { None };
+        if replacements.next().is_some() {
+            return None;
+        }
+        let any_changed = input.is_some() || scale.is_some() || bias.as_ref().is_some_and(Option::is_some);
+        if !any_changed {
+            return None;
+        }
+        let input = input.unwrap_or_else(|| self.input.clone());
+        let scale = scale.unwrap_or_else(|| self.scale.clone());
+        let bias = bias.flatten().or_else(|| self.bias.clone());
+        Some(
+            LayerNormalization::new(self.name.clone(), input, scale, bias, self.axis, self.epsilon, self.stash_type)
+                .expect("optimize only substitutes same-shape/dtype tensors") as Arc<dyn Tensor>,
+        )
+    }
+}
+
+impl SingleOutputNode for LayerNormalization {
+    fn get_output_shape(&self) -> &Shape {
+        self.input.shape()
+    }
+
+    fn get_output_dtype(&self) -> DType {
+        self.input.dtype()
+    }
+}
+
+pub struct GroupNormalization {
+    name: Option<String>,
+    input: Arc<dyn Tensor>,
+    scale: Arc<dyn Tensor>,
+    bias: Arc<dyn Tensor>,
+    num_groups: i64,
+    epsilon: f32,
+}
+
+impl GroupNormalization {
+    pub fn new(
+        name: Option<String>,
+        input: Arc<dyn Tensor>,
+        scale: Arc<dyn Tensor>,
+        bias: Arc<dyn Tensor>,
+        num_groups: i64,
+        epsilon: f32,
+    ) -> Result<Arc<Self>, Error> {
+        Ok(Arc::new(Self { name, input, scale, bias, num_groups, epsilon }))
+    }
+}
+
+impl Node for GroupNormalization {
+    fn get_input_tensors(&self) -> Vec<&dyn Tensor> {
+        vec![self.input.as_ref(), self.scale.as_ref(), self.bias.as_ref()]
+    }
+
+    fn get_output_tensors(&self) -> Vec<&dyn Tensor> {
+        vec![self as &dyn Tensor]
+    }
+
+    fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn get_onnx_type(&self) -> &str {
+        "GroupNormalization"
+    }
+
+    fn get_onnx_attributes(&self) -> Vec<crate::onnx::AttributeProto> {
+        vec![int_attribute("num_groups", self.num_groups), float_attribute("epsilon", self.epsilon)]
+    }
+
+    fn with_inputs(&self, replacements: Vec<Option<Arc<dyn Tensor>>>) -> Option<Arc<dyn Tensor>> {
+        let [input, scale, bias]: [Option<Arc<dyn Tensor>>; 3] = replacements.try_into().ok()?;
+        if input.is_none() && scale.is_none() && bias.is_none() {
+            return None;
+        }
+        let input = input.unwrap_or_else(|| self.input.clone());
+        let scale = scale.unwrap_or_else(|| self.scale.clone());
+        let bias = bias.unwrap_or_else(|| self.bias.clone());
+        Some(
+            GroupNormalization::new(self.name.clone(), input, scale, bias, self.num_groups, self.epsilon)
+                .expect("optimize only substitutes same-shape/dtype tensors") as Arc<dyn Tensor>,
+        )
+    }
+}
+
+impl SingleOutputNode for GroupNormalization {
+    fn get_output_shape(&self) -> &Shape {
+        self.input.shape()
+    }
+
+    fn get_output_dtype(&self) -> DType {
+        self.input.dtype()
+    }
+}
+
+pub struct RMSNormalization {
+    name: Option<String>,
+    input: Arc<dyn Tensor>,
+    scale: Arc<dyn Tensor>,
+    epsilon: f32,
+    axis: i64,
+}
+
+impl RMSNormalization {
+    pub fn new(
+        name: Option<String>,
+        input: Arc<dyn Tensor>,
+        scale: Arc<dyn Tensor>,
+        epsilon: f32,
+        axis: i64,
+    ) -> Result<Arc<Self>, Error> {
+        Ok(Arc::new(Self { name, input, scale, epsilon, axis }))
+    }
+}
+
+impl Node for RMSNormalization {
+    fn get_input_tensors(&self) -> Vec<&dyn Tensor> {
+        vec![self.input.as_ref(), self.scale.as_ref()]
+    }
+
+    fn get_output_tensors(&self) -> Vec<&dyn Tensor> {
+        vec![self as &dyn Tensor]
+    }
+
+    fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn get_onnx_type(&self) -> &str {
+        "RMSNormalization"
+    }
+
+    fn get_onnx_attributes(&self) -> Vec<crate::onnx::AttributeProto> {
+        vec![float_attribute("epsilon", self.epsilon), int_attribute("axis", self.axis)]
+    }
+
+    fn with_inputs(&self, replacements: Vec<Option<Arc<dyn Tensor>>>) -> Option<Arc<dyn Tensor>> {
+        let [input, scale]: [Option<Arc<dyn Tensor>>; 2] = replacements.try_into().ok()?;
+        if input.is_none() && scale.is_none() {
+            return None;
+        }
+        let input = input.unwrap_or_else(|| self.input.clone());
+        let scale = scale.unwrap_or_else(|| self.scale.clone());
+        Some(
+            RMSNormalization::new(self.name.clone(), input, scale, self.epsilon, self.axis)
+                .expect("optimize only substitutes same-shape/dtype tensors") as Arc<dyn Tensor>,
+        )
+    }
+}
+
+impl SingleOutputNode for RMSNormalization {
+    fn get_output_shape(&self) -> &Shape {
+        self.input.shape()
+    }
+
+    fn get_output_dtype(&self) -> DType {
+        self.input.dtype()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tensor::InputTensor;
+
+    fn input(name: &str, shape: &[usize]) -> Arc<dyn Tensor> {
+        Arc::new(InputTensor::new(name.to_string(), Shape::from(shape), DType::F32)) as Arc<dyn Tensor>
+    }
+
+    #[test]
+    fn matmul_output_shape_broadcasts_size_one_batch_dims() {
+        let shape = matmul_output_shape(&Shape::from(&[1, 2, 3][..]), &Shape::from(&[5, 3, 6][..])).unwrap();
+        assert_eq!(shape, Shape::from(&[5, 2, 6][..]));
+
+        let shape = matmul_output_shape(&Shape::from(&[5, 2, 3][..]), &Shape::from(&[1, 3, 6][..])).unwrap();
+        assert_eq!(shape, Shape::from(&[5, 2, 6][..]));
+    }
+
+    #[test]
+    fn matmul_output_shape_rejects_mismatched_batch_dims() {
+        let err = matmul_output_shape(&Shape::from(&[5, 2, 3][..]), &Shape::from(&[3, 3, 6][..])).unwrap_err();
+        assert!(matches!(err, Error::IncompatibleShapeError(_, _)));
+    }
+
+    #[test]
+    fn matmul_new_broadcasts_batch_dims_across_operands() {
+        let a = input("a", &[5, 2, 3]);
+        let b = input("b", &[3, 6]);
+        let out = MatMul::new(None, a, b).unwrap();
+        assert_eq!(out.get_output_shape(), &Shape::from(&[5, 2, 6][..]));
+    }
+
+    #[test]
+    fn matmul_new_rejects_incompatible_batch_dims() {
+        let a = input("a", &[5, 2, 3]);
+        let b = input("b", &[3, 3, 6]);
+        assert!(MatMul::new(None, a, b).is_err());
+    }
+
+    #[test]
+    fn constant_get_initializer_embeds_its_data_inline() {
+        let c = Constant::new(None, TensorData::fill(Shape::from(&[2][..]), 1.5f32).unwrap());
+        let mut manager = crate::weights::NullOutputManager::new();
+        let proto = c.get_initializer("weight".to_string(), &mut manager).unwrap().unwrap();
+        assert_eq!(proto.name, "weight");
+        assert_eq!(proto.float_data, vec![1.5, 1.5]);
+    }
+}
@@ -0,0 +1,102 @@
+//! Resolves the minimum ONNX opset version needed per domain, from the set of
+//! node types actually used in a graph, so `build_proto` can populate
+//! `opset_import` instead of leaving it empty.
+use std::collections::{HashMap, HashSet};
+
+use crate::node::Node;
+use crate::onnx::OperatorSetIdProto;
+use crate::Error;
+
+/// Minimum opset version an op needs, below which it isn't guaranteed to exist or
+/// have its current semantics. Ops not listed default to 1, the oldest version of
+/// any domain.
+fn min_version(domain: &str, op_type: &str) -> i64 {
+    match (domain, op_type) {
+        ("ai.onnx", "LayerNormalization") => 17,
+        ("ai.onnx", "GroupNormalization") => 18,
+        ("ai.onnx", "CumSum") => 11,
+        ("ai.onnx", "Expand") => 8,
+        ("ai.onnx", "Sigmoid") => 6,
+        ("ai.onnx", "ReduceSum") => 13,
+        ("ai.onnx", "ReduceMax") => 18,
+        // `pytorch::squeeze`/`unsqueeze` always pass axes as a `Constant`
+        // input, the form these ops only gained in opset 13.
+        ("ai.onnx", "Squeeze") => 13,
+        ("ai.onnx", "Unsqueeze") => 13,
+        // `Slice::new` only supports the input-based start/end/axes/steps
+        // form, which needs opset 10.
+        ("ai.onnx", "Slice") => 10,
+        _ => 1,
+    }
+}
+
+/// Collects the domains used across `nodes` and resolves a minimum opset version
+/// for each, honoring `overrides` (domain -> pinned version). Errors if a pinned
+/// version is older than what an op used in that domain actually requires.
+pub(crate) fn resolve_opset_imports(
+    nodes: &HashSet<&dyn Node>,
+    overrides: &HashMap<String, i64>,
+) -> Result<Vec<OperatorSetIdProto>, Error> {
+    let mut required: HashMap<String, i64> = HashMap::new();
+    for node in nodes {
+        let domain = node.get_onnx_domain().to_string();
+        let needed = min_version(&domain, node.get_onnx_type());
+        let entry = required.entry(domain).or_insert(needed);
+        *entry = (*entry).max(needed);
+    }
+
+    let mut resolved: Vec<OperatorSetIdProto> = Vec::with_capacity(required.len());
+    for (domain, min_required) in required {
+        let version = match overrides.get(&domain) {
+            Some(&pinned) if pinned < min_required => {
+                return Err(Error::UnsupportedOpsetError(domain, pinned, min_required));
+            }
+            Some(&pinned) => pinned,
+            None => min_required,
+        };
+        resolved.push(OperatorSetIdProto { domain, version });
+    }
+    resolved.sort_by(|a, b| a.domain.cmp(&b.domain));
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operators::{Constant, ReduceSum};
+    use crate::tensor::{Shape, Tensor, TensorData};
+    use std::sync::Arc;
+
+    fn scalar_constant(value: f32) -> Arc<dyn Tensor> {
+        Constant::new(None, TensorData::fill(Shape::new(vec![]), value).unwrap()) as Arc<dyn Tensor>
+    }
+
+    #[test]
+    fn reduce_sum_requires_opset_13() {
+        let node = ReduceSum::new(None, scalar_constant(1.0), scalar_constant(0.0), 0).unwrap();
+        let mut nodes = HashSet::new();
+        nodes.insert(node.as_ref() as &dyn Node);
+        let imports = resolve_opset_imports(&nodes, &HashMap::new()).unwrap();
+        assert_eq!(imports, vec![OperatorSetIdProto { domain: "ai.onnx".to_string(), version: 13 }]);
+    }
+
+    #[test]
+    fn pinned_override_older_than_required_errors() {
+        let node = ReduceSum::new(None, scalar_constant(1.0), scalar_constant(0.0), 0).unwrap();
+        let mut nodes = HashSet::new();
+        nodes.insert(node.as_ref() as &dyn Node);
+        let mut overrides = HashMap::new();
+        overrides.insert("ai.onnx".to_string(), 9);
+        assert!(resolve_opset_imports(&nodes, &overrides).is_err());
+    }
+
+    #[test]
+    fn unlisted_op_defaults_to_opset_1() {
+        let node = scalar_constant(1.0);
+        let producer = node.get_producing_node().unwrap();
+        let mut nodes = HashSet::new();
+        nodes.insert(producer);
+        let imports = resolve_opset_imports(&nodes, &HashMap::new()).unwrap();
+        assert_eq!(imports, vec![OperatorSetIdProto { domain: "ai.onnx".to_string(), version: 1 }]);
+    }
+}
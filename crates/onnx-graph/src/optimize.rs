@@ -0,0 +1,132 @@
+//! Pre-`build_proto` graph optimizations: constant folding and MatMul-unary fusion.
+//!
+//! Callers slot this in front of `build_proto`, e.g.
+//! `build_proto(&inputs, &optimize(&outputs)?, weight_storage, &opset_overrides)`.
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::node::{topological_order, Node};
+use crate::operators::{Constant, MatMul, MatMulUnary};
+use crate::tensor::Tensor;
+use crate::Error;
+
+/// Walks every node reachable from `outputs` in topological order, replacing
+/// each one whose output is fully knowable (`Node::resolve_output_data`) with
+/// a `Constant`, and rewiring consumers of a replaced tensor via
+/// `Node::with_inputs` (e.g. `MatMul::with_inputs` additionally takes the
+/// opportunity to fuse into `MatMulUnary` once its `a` operand is constant).
+/// A node untouched by either step passes through unchanged.
+pub fn optimize(outputs: &[(String, Arc<dyn Tensor>)]) -> Result<Vec<(String, Arc<dyn Tensor>)>, Error> {
+    let mut nodes = HashSet::new();
+    for (_, tensor) in outputs {
+        tensor.get_nodes(&mut nodes);
+    }
+    let order = topological_order(&nodes);
+
+    let mut replacements: HashMap<*const dyn Tensor, Arc<dyn Tensor>> = HashMap::new();
+    for node in order {
+        let [output] = node.get_output_tensors().as_slice() else {
+            // Multi-output nodes aren't rewired by this pass; no node type
+            // needs it yet, and `with_inputs` only rebuilds single-output nodes.
+            continue;
+        };
+
+        if let Some(data) = node.resolve_output_data() {
+            replacements.insert(*output as *const dyn Tensor, Constant::new(None, data) as Arc<dyn Tensor>);
+            continue;
+        }
+
+        let substitutions: Vec<_> = node
+            .get_input_tensors()
+            .iter()
+            .map(|input| replacements.get(&(*input as *const dyn Tensor)).cloned())
+            .collect();
+        if substitutions.iter().any(Option::is_some) {
+            if let Some(rebuilt) = node.with_inputs(substitutions) {
+                replacements.insert(*output as *const dyn Tensor, rebuilt);
+            }
+        }
+    }
+
+    Ok(outputs
+        .iter()
+        .map(|(name, tensor)| {
+            let resolved = replacements
+                .get(&(tensor.as_ref() as *const dyn Tensor))
+                .cloned()
+                .unwrap_or_else(|| tensor.clone());
+            (name.clone(), resolved)
+        })
+        .collect())
+}
+
+/// Ports tract's `MatMulUnary`: when a `MatMul`'s `a` operand is constant (the
+/// common case coming out of `pytorch::linear`, where `a` is the loaded weight),
+/// fuse it into a dedicated node carrying the constant matrix directly plus an
+/// optional fused bias `Add`, instead of a plain `MatMul` the exporter has to
+/// re-discover is weight-constant later. Called from `MatMul::with_inputs`, so
+/// it only runs as part of the opt-in `optimize` pass above, never at graph
+/// construction time.
+pub(crate) fn fuse_matmul_unary(
+    name: Option<String>,
+    a: Arc<dyn Tensor>,
+    b: Arc<dyn Tensor>,
+    bias: Option<Arc<dyn Tensor>>,
+) -> Result<Arc<dyn Tensor>, Error> {
+    let Some(constant_a) = a.get_producing_node().and_then(|node| node.resolve_output_data()) else {
+        // `a` isn't constant here; nothing to fuse, fall back to a plain MatMul.
+        return Ok(MatMul::new(name, a, b)? as Arc<dyn Tensor>);
+    };
+    // MatMul's contraction dimension: `constant_a`'s last axis against `b`'s
+    // second-to-last. A `constant_a` of rank below 2 is rejected by
+    // `MatMulUnary::new`'s shape inference.
+    let (a_rank, b_rank) = (constant_a.shape().rank(), b.rank());
+    if a_rank < 1 || b_rank < 2 {
+        return Err(Error::IncompatibleShapeError(constant_a.shape().clone(), b.shape().clone()));
+    }
+    let a_inner = constant_a.shape().dims()[a_rank - 1].value();
+    let b_inner = b.shape().dims()[b_rank - 2].value();
+    if a_inner.is_none() || a_inner != b_inner {
+        return Err(Error::IncompatibleShapeError(constant_a.shape().clone(), b.shape().clone()));
+    }
+    Ok(MatMulUnary::new(name, constant_a, b, bias)? as Arc<dyn Tensor>)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operators::Add;
+    use crate::tensor::{Shape, TensorData};
+
+    #[test]
+    fn folds_constant_subgraph_feeding_a_non_constant_output() {
+        let a = Constant::new(None, TensorData::fill(Shape::new(vec![]), 2.0f32).unwrap()) as Arc<dyn Tensor>;
+        let b = Constant::new(None, TensorData::fill(Shape::new(vec![]), 3.0f32).unwrap()) as Arc<dyn Tensor>;
+        let folded_input = Add::new(None, a, b).unwrap() as Arc<dyn Tensor>;
+
+        let runtime_input = crate::tensor::InputTensor::new("x".to_string(), Shape::new(vec![]), crate::DType::F32);
+        let output = Add::new(None, folded_input, Arc::new(runtime_input) as Arc<dyn Tensor>).unwrap() as Arc<dyn Tensor>;
+
+        let optimized = optimize(&[("y".to_string(), output)]).unwrap();
+        let producing = optimized[0].1.get_producing_node().unwrap();
+
+        // The top-level output itself still depends on the runtime input, so it
+        // can't fold to a `Constant`, but its constant-only operand should have
+        // been replaced by one, via `Add::with_inputs`.
+        assert_eq!(producing.get_onnx_type(), "Add");
+        let inputs = producing.get_input_tensors();
+        let folded_operand = inputs.iter().find(|t| t.get_producing_node().is_some()).unwrap();
+        assert_eq!(folded_operand.get_producing_node().unwrap().resolve_output_data().unwrap().to_f64_vec(), vec![5.0]);
+    }
+
+    #[test]
+    fn fuses_matmul_with_constant_weight_into_matmul_unary() {
+        let weight = Constant::new(None, TensorData::fill(Shape::new(vec![4, 3]), 1.0f32).unwrap()) as Arc<dyn Tensor>;
+        let input = Arc::new(crate::tensor::InputTensor::new("x".to_string(), Shape::new(vec![3, 2]), crate::DType::F32)) as Arc<dyn Tensor>;
+        let output = MatMul::new(None, weight, input).unwrap() as Arc<dyn Tensor>;
+
+        let optimized = optimize(&[("y".to_string(), output)]).unwrap();
+
+        assert_eq!(optimized[0].1.get_producing_node().unwrap().get_onnx_type(), "MatMulUnary");
+    }
+}
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 use crate::{operators, Error};
-use crate::operators::{Cast, Constant, CumSum, Div, Expand, GroupNormalization, LayerNormalization, Mul, RMSNormalization, Reshape, Sigmoid, Slice, Squeeze, Transpose, Unsqueeze};
+use crate::operators::{Cast, Constant, CumSum, Div, Exp, Expand, GroupNormalization, LayerNormalization, Mul, Neg, RMSNormalization, ReduceMax, ReduceSum, Reshape, Sigmoid, Slice, Squeeze, Sub, Transpose, Unsqueeze};
 use crate::tensor::{DType, Dimension, Shape, Tensor, TensorData, TensorDataValue};
 use crate::weights::WeightManager;
 
@@ -11,7 +11,7 @@ pub fn linear(weight_manager: &impl WeightManager, input: Arc<dyn Tensor>) -> Re
     let mat_out = operators::MatMul::new(
         weight_manager.get_prefix().map(|x| x.to_string()),
         weight_manager.get_tensor("weight")?,
-        input
+        input,
     )?;
     let mat_out = squeeze(mat_out, extra_axis_idx as i64)?;
     if let Some(bias) = bias {
@@ -127,4 +127,111 @@ pub fn expand(input: Arc<dyn Tensor>, dims: Vec<i64>) -> Result<Arc<Expand>, Err
     let shape = Shape::from(&[dims.len()][..]);
     let c = Constant::new(None, TensorData::new(dims.into(), shape)?);
     Ok(Expand::new(None, input, c)?)
-}
\ No newline at end of file
+}
+
+pub fn reduce_max(input: Arc<dyn Tensor>, axis: i64, keepdims: bool) -> Result<Arc<ReduceMax>, Error> {
+    let shape = Shape::new(vec![Dimension::new(Some(1), None, None)]);
+    let axes = Constant::new(None, TensorData::fill(shape, axis)?);
+    Ok(ReduceMax::new(None, input, axes, keepdims as i64)?)
+}
+
+pub fn reduce_sum(input: Arc<dyn Tensor>, axis: i64, keepdims: bool) -> Result<Arc<ReduceSum>, Error> {
+    let shape = Shape::new(vec![Dimension::new(Some(1), None, None)]);
+    let axes = Constant::new(None, TensorData::fill(shape, axis)?);
+    Ok(ReduceSum::new(None, input, axes, keepdims as i64)?)
+}
+
+/// Standard softmax: `exp(x_i - m) / sum_j exp(x_j - m)`, `m = max_j x_j`.
+pub fn softmax(input: Arc<dyn Tensor>, axis: i64) -> Result<Arc<dyn Tensor>, Error> {
+    let m = reduce_max(input.clone(), axis, true)?;
+    let shifted = Sub::new(None, input, m)?;
+    let exp = Exp::new(None, shifted);
+    let sum = reduce_sum(exp.clone(), axis, true)?;
+    Ok(Div::new(None, exp, sum)?)
+}
+
+/// "Softmax-1"/quiet softmax: like [`softmax`], but the denominator also carries
+/// an `exp(-m)` term, the shifted-form equivalent of adding 1 before normalizing.
+/// That lets a whole output row go to zero so attention can attend to nothing,
+/// and keeps large outlier activations from dominating the normalization.
+pub fn quiet_softmax(input: Arc<dyn Tensor>, axis: i64) -> Result<Arc<dyn Tensor>, Error> {
+    let m = reduce_max(input.clone(), axis, true)?;
+    let shifted = Sub::new(None, input, m.clone())?;
+    let exp = Exp::new(None, shifted);
+    let sum = reduce_sum(exp.clone(), axis, true)?;
+    let exp_neg_m = Exp::new(None, Neg::new(None, m));
+    let denom = operators::Add::new(None, sum, exp_neg_m)?;
+    Ok(Div::new(None, exp, denom)?)
+}
+
+/// `softmax(Q K^T / sqrt(head_dim)) V`, using [`quiet_softmax`] so a query can
+/// attend to nothing instead of being forced onto a uniform distribution.
+pub fn scaled_dot_product_attention(
+    query: Arc<dyn Tensor>,
+    key: Arc<dyn Tensor>,
+    value: Arc<dyn Tensor>,
+    head_dim: usize,
+) -> Result<Arc<dyn Tensor>, Error> {
+    let scores = operators::MatMul::new(None, query, transpose(key))?;
+    let scores = div_scalar(scores, (head_dim as f32).sqrt())?;
+    let weights = quiet_softmax(scores, -1)?;
+    Ok(operators::MatMul::new(None, weights, value)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tensor::InputTensor;
+
+    fn input(name: &str, shape: &[usize]) -> Arc<dyn Tensor> {
+        Arc::new(InputTensor::new(name.to_string(), Shape::from(shape), DType::F32)) as Arc<dyn Tensor>
+    }
+
+    #[test]
+    fn softmax_preserves_shape_and_divides_by_a_plain_sum() {
+        let x = input("x", &[2, 3]);
+        let out = softmax(x, -1).unwrap();
+
+        assert_eq!(out.shape(), &Shape::from(&[2, 3][..]));
+        let div = out.get_producing_node().unwrap();
+        assert_eq!(div.get_onnx_type(), "Div");
+        let denom = div.get_input_tensors()[1];
+        assert_eq!(denom.get_producing_node().unwrap().get_onnx_type(), "ReduceSum");
+    }
+
+    #[test]
+    fn quiet_softmax_denominator_carries_the_extra_exp_neg_m_term() {
+        // Unlike `softmax`, whose denominator is the bare `ReduceSum`, quiet
+        // softmax's denominator is `sum + exp(-m)` so a whole row can decay to
+        // zero instead of always normalizing to a distribution that sums to 1.
+        let x = input("x", &[2, 3]);
+        let out = quiet_softmax(x, -1).unwrap();
+
+        assert_eq!(out.shape(), &Shape::from(&[2, 3][..]));
+        let div = out.get_producing_node().unwrap();
+        assert_eq!(div.get_onnx_type(), "Div");
+
+        let denom = div.get_input_tensors()[1].get_producing_node().unwrap();
+        assert_eq!(denom.get_onnx_type(), "Add");
+        let denom_inputs = denom.get_input_tensors();
+        assert_eq!(denom_inputs[0].get_producing_node().unwrap().get_onnx_type(), "ReduceSum");
+        assert_eq!(denom_inputs[1].get_producing_node().unwrap().get_onnx_type(), "Exp");
+    }
+
+    #[test]
+    fn scaled_dot_product_attention_output_shape_is_queries_by_value_dim() {
+        let query = input("q", &[2, 4]);
+        let key = input("k", &[3, 4]);
+        let value = input("v", &[3, 5]);
+
+        let out = scaled_dot_product_attention(query, key, value, 4).unwrap();
+
+        assert_eq!(out.shape(), &Shape::from(&[2, 5][..]));
+        let out_node = out.get_producing_node().unwrap();
+        assert_eq!(out_node.get_onnx_type(), "MatMul");
+        // The attention weights feeding the final MatMul should come from
+        // `quiet_softmax` (a `Div`), not a plain `MatMul` of raw scores.
+        let weights = out_node.get_input_tensors()[0];
+        assert_eq!(weights.get_producing_node().unwrap().get_onnx_type(), "Div");
+    }
+}
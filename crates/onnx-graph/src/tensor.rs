@@ -0,0 +1,361 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use crate::node::{Node, SingleOutputNode};
+use crate::weights::WeightExternalOutputManager;
+use crate::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DType {
+    Bool,
+    F16,
+    F32,
+    F64,
+    I32,
+    I64,
+}
+
+impl fmt::Display for DType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Dimension {
+    value: Option<i64>,
+    param: Option<String>,
+    denotation: Option<String>,
+}
+
+impl Dimension {
+    pub fn new(value: Option<i64>, param: Option<String>, denotation: Option<String>) -> Self {
+        Self { value, param, denotation }
+    }
+
+    pub fn value(&self) -> Option<i64> {
+        self.value
+    }
+
+    pub fn param(&self) -> Option<&str> {
+        self.param.as_deref()
+    }
+
+    pub fn denotation(&self) -> Option<&str> {
+        self.denotation.as_deref()
+    }
+}
+
+impl fmt::Display for Dimension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.value, &self.param) {
+            (Some(v), _) => write!(f, "{}", v),
+            (None, Some(p)) => write!(f, "{}", p),
+            (None, None) => write!(f, "?"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Shape(Vec<Dimension>);
+
+impl Shape {
+    pub fn new(dims: Vec<Dimension>) -> Self {
+        Self(dims)
+    }
+
+    pub fn dims(&self) -> &[Dimension] {
+        &self.0
+    }
+
+    pub fn rank(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Total element count, treating an unresolved dimension as size 1. Only
+    /// meaningful for shapes that are fully resolved, e.g. a `Constant`'s.
+    pub fn numel(&self) -> usize {
+        self.0.iter().map(|d| d.value().unwrap_or(1).max(0) as usize).product()
+    }
+}
+
+impl From<&[usize]> for Shape {
+    fn from(dims: &[usize]) -> Self {
+        Shape(dims.iter().map(|&d| Dimension::new(Some(d as i64), None, None)).collect())
+    }
+}
+
+impl fmt::Display for Shape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+        for (i, dim) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", dim)?;
+        }
+        write!(f, "]")
+    }
+}
+
+/// The typed backing storage for a [`TensorData`], covering the numeric kinds
+/// the crate actually constructs constants/fills with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TensorDataValue {
+    F32(Vec<f32>),
+    F64(Vec<f64>),
+    I32(Vec<i32>),
+    I64(Vec<i64>),
+}
+
+impl TensorDataValue {
+    fn len(&self) -> usize {
+        match self {
+            TensorDataValue::F32(v) => v.len(),
+            TensorDataValue::F64(v) => v.len(),
+            TensorDataValue::I32(v) => v.len(),
+            TensorDataValue::I64(v) => v.len(),
+        }
+    }
+
+    fn dtype(&self) -> DType {
+        match self {
+            TensorDataValue::F32(_) => DType::F32,
+            TensorDataValue::F64(_) => DType::F64,
+            TensorDataValue::I32(_) => DType::I32,
+            TensorDataValue::I64(_) => DType::I64,
+        }
+    }
+
+    fn to_f64_vec(&self) -> Vec<f64> {
+        match self {
+            TensorDataValue::F32(v) => v.iter().map(|&x| x as f64).collect(),
+            TensorDataValue::F64(v) => v.clone(),
+            TensorDataValue::I32(v) => v.iter().map(|&x| x as f64).collect(),
+            TensorDataValue::I64(v) => v.iter().map(|&x| x as f64).collect(),
+        }
+    }
+}
+
+impl From<Vec<f32>> for TensorDataValue {
+    fn from(v: Vec<f32>) -> Self {
+        TensorDataValue::F32(v)
+    }
+}
+
+impl From<Vec<f64>> for TensorDataValue {
+    fn from(v: Vec<f64>) -> Self {
+        TensorDataValue::F64(v)
+    }
+}
+
+impl From<Vec<i32>> for TensorDataValue {
+    fn from(v: Vec<i32>) -> Self {
+        TensorDataValue::I32(v)
+    }
+}
+
+impl From<Vec<i64>> for TensorDataValue {
+    fn from(v: Vec<i64>) -> Self {
+        TensorDataValue::I64(v)
+    }
+}
+
+/// Resolved tensor data: a [`Shape`] plus the values backing it, used for
+/// constants and for round-tripping data in/out of `Executor::execute`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TensorData {
+    shape: Shape,
+    data: TensorDataValue,
+}
+
+impl TensorData {
+    pub fn new(data: TensorDataValue, shape: Shape) -> Result<Self, Error> {
+        if data.len() != shape.numel() {
+            return Err(Error::InputShapeError(shape));
+        }
+        Ok(Self { shape, data })
+    }
+
+    /// Fills `shape` with `value` repeated `shape.numel()` times.
+    pub fn fill<T>(shape: Shape, value: T) -> Result<Self, Error>
+    where
+        T: Copy,
+        TensorDataValue: From<Vec<T>>,
+    {
+        let numel = shape.numel();
+        TensorData::new(vec![value; numel].into(), shape)
+    }
+
+    pub fn shape(&self) -> &Shape {
+        &self.shape
+    }
+
+    pub fn dtype(&self) -> DType {
+        self.data.dtype()
+    }
+
+    pub fn to_f64_vec(&self) -> Vec<f64> {
+        self.data.to_f64_vec()
+    }
+
+    pub fn as_raw_bytes(&self) -> Vec<u8> {
+        match &self.data {
+            TensorDataValue::F32(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+            TensorDataValue::F64(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+            TensorDataValue::I32(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+            TensorDataValue::I64(v) => v.iter().flat_map(|x| x.to_le_bytes()).collect(),
+        }
+    }
+
+    pub fn to_tensor_proto(&self, name: String) -> crate::onnx::TensorProto {
+        let dims = self.shape.dims().iter().map(|d| d.value().unwrap_or(1)).collect();
+        let mut proto = crate::onnx::TensorProto {
+            name,
+            dims,
+            data_type: match self.data.dtype() {
+                DType::F32 => crate::onnx::tensor_proto::DataType::Float as i32,
+                DType::F64 => crate::onnx::tensor_proto::DataType::Double as i32,
+                DType::I32 => crate::onnx::tensor_proto::DataType::Int32 as i32,
+                DType::I64 => crate::onnx::tensor_proto::DataType::Int64 as i32,
+                DType::F16 => crate::onnx::tensor_proto::DataType::Float16 as i32,
+                DType::Bool => crate::onnx::tensor_proto::DataType::Bool as i32,
+            },
+            ..Default::default()
+        };
+        match &self.data {
+            TensorDataValue::F32(v) => proto.float_data = v.clone(),
+            TensorDataValue::F64(v) => proto.double_data = v.clone(),
+            TensorDataValue::I32(v) => proto.int32_data = v.clone(),
+            TensorDataValue::I64(v) => proto.int64_data = v.clone(),
+        }
+        proto
+    }
+}
+
+pub trait Tensor {
+    fn dtype(&self) -> DType;
+    fn shape(&self) -> &Shape;
+
+    fn rank(&self) -> usize {
+        self.shape().rank()
+    }
+
+    fn get_name(&self) -> Option<&str> {
+        None
+    }
+
+    /// Collects the node that produces this tensor (and everything upstream of
+    /// it) into `table`. A tensor with no producing node (e.g. a graph input)
+    /// contributes nothing.
+    fn get_nodes<'a>(&'a self, table: &mut HashSet<&'a dyn Node>) {
+        let _ = table;
+    }
+
+    fn get_sub_tensors<'a>(&'a self, table: &mut HashSet<&'a dyn Tensor>) {
+        let _ = table;
+    }
+
+    /// The single node that produces this tensor, if any (a tensor with no
+    /// producing node, e.g. a graph input, returns `None`). Unlike
+    /// `get_nodes`, this walks one edge rather than the whole upstream
+    /// closure, so callers that just need to step to the next node (e.g.
+    /// `topological_order`) don't pay for traversing ancestors they're about
+    /// to visit anyway.
+    fn get_producing_node(&self) -> Option<&dyn Node> {
+        None
+    }
+
+    fn gather_weights(&self, manager: &mut dyn WeightExternalOutputManager) {
+        let _ = manager;
+    }
+
+    fn get_initializer(
+        &self,
+        name: String,
+        manager: &mut dyn WeightExternalOutputManager,
+    ) -> Result<Option<crate::onnx::TensorProto>, Error> {
+        let _ = (name, manager);
+        Ok(None)
+    }
+
+    fn to_value_info_proto(&self, name: String) -> crate::onnx::ValueInfoProto {
+        crate::onnx::ValueInfoProto { name, ..Default::default() }
+    }
+}
+
+/// Every `SingleOutputNode` is usable as the `Tensor` it produces, so operator
+/// structs only need to implement `Node`/`SingleOutputNode` once.
+impl<T: SingleOutputNode + 'static> Tensor for T {
+    fn dtype(&self) -> DType {
+        self.get_output_dtype()
+    }
+
+    fn shape(&self) -> &Shape {
+        self.get_output_shape()
+    }
+
+    fn get_nodes<'a>(&'a self, table: &mut HashSet<&'a dyn Node>) {
+        Node::get_nodes(self, table);
+    }
+
+    fn get_sub_tensors<'a>(&'a self, table: &mut HashSet<&'a dyn Tensor>) {
+        self.get_tensors(table);
+    }
+
+    fn get_producing_node(&self) -> Option<&dyn Node> {
+        Some(self as &dyn Node)
+    }
+
+    fn get_initializer(
+        &self,
+        name: String,
+        manager: &mut dyn WeightExternalOutputManager,
+    ) -> Result<Option<crate::onnx::TensorProto>, Error> {
+        SingleOutputNode::get_initializer(self, name, manager)
+    }
+}
+
+/// A graph input: a named, shaped, typed tensor with no producing node.
+pub struct InputTensor {
+    name: String,
+    shape: Shape,
+    dtype: DType,
+}
+
+impl InputTensor {
+    pub fn new(name: String, shape: Shape, dtype: DType) -> Self {
+        Self { name, shape, dtype }
+    }
+}
+
+impl Tensor for InputTensor {
+    fn dtype(&self) -> DType {
+        self.dtype
+    }
+
+    fn shape(&self) -> &Shape {
+        &self.shape
+    }
+
+    fn get_name(&self) -> Option<&str> {
+        Some(&self.name)
+    }
+}
+
+impl<'a> PartialEq for &'a dyn Tensor {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::addr_eq(*self, *other)
+    }
+}
+
+impl<'a> Eq for &'a dyn Tensor {}
+
+impl<'a> Hash for &'a dyn Tensor {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let a: *const _ = *self;
+        let address: *const u8 = a.cast();
+        state.write_usize(address.addr());
+    }
+}
@@ -0,0 +1,99 @@
+//! Numerical validation: compares a graph's computed outputs against a reference
+//! within a dtype-aware tolerance.
+use crate::tensor::{DType, TensorData};
+use crate::Error;
+
+/// How strict a numerical comparison should be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Approximation {
+    /// Zero tolerance: `atol` and `rtol` are both 0.
+    Exact,
+    /// Tight enough to catch a real regression, loose enough to survive
+    /// reordering of floating-point reductions.
+    Close,
+    /// Loose enough to survive a different backend's kernel implementation.
+    Approximate,
+}
+
+impl Approximation {
+    /// `(atol, rtol)` for comparing values of `dtype` at this approximation level.
+    pub fn atol_and_rtol(&self, dtype: DType) -> (f64, f64) {
+        match self {
+            Approximation::Exact => (0.0, 0.0),
+            Approximation::Close => match dtype {
+                DType::F16 => (1e-3, 1e-3),
+                _ => (1e-7, 1e-7),
+            },
+            Approximation::Approximate => match dtype {
+                DType::F16 => (1e-3, 5e-3),
+                _ => (1e-4, 5e-4),
+            },
+        }
+    }
+}
+
+/// Compares `actual` against `expected` element-wise, failing on the first index
+/// where `|a - b| > atol + rtol * |b|`.
+pub fn compare(approximation: Approximation, actual: &TensorData, expected: &TensorData) -> Result<(), Error> {
+    if actual.shape() != expected.shape() {
+        return Err(Error::ShapeMismatchError(actual.shape().clone(), expected.shape().clone()));
+    }
+    if actual.dtype() != expected.dtype() {
+        return Err(Error::DTypeMismatchError(actual.dtype(), expected.dtype()));
+    }
+
+    let (atol, rtol) = approximation.atol_and_rtol(actual.dtype());
+    for (index, (a, b)) in actual.to_f64_vec().iter().zip(expected.to_f64_vec().iter()).enumerate() {
+        let tolerance = atol + rtol * b.abs();
+        if (a - b).abs() > tolerance {
+            return Err(Error::ValueMismatchError(index, *a, *b));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tensor::Shape;
+
+    fn vector(values: Vec<f32>) -> TensorData {
+        let shape = Shape::new(vec![crate::tensor::Dimension::new(Some(values.len() as i64), None, None)]);
+        TensorData::new(values.into(), shape).unwrap()
+    }
+
+    #[test]
+    fn exact_requires_bit_for_bit_equality() {
+        assert_eq!(Approximation::Exact.atol_and_rtol(DType::F32), (0.0, 0.0));
+        assert!(compare(Approximation::Exact, &vector(vec![1.0]), &vector(vec![1.0 + f32::EPSILON])).is_err());
+    }
+
+    #[test]
+    fn approximate_tolerates_small_drift() {
+        let actual = vector(vec![1.0001]);
+        let expected = vector(vec![1.0]);
+        assert!(compare(Approximation::Approximate, &actual, &expected).is_ok());
+        assert!(compare(Approximation::Close, &actual, &expected).is_err());
+    }
+
+    #[test]
+    fn mismatched_shapes_error_before_comparing_values() {
+        let actual = vector(vec![1.0, 2.0]);
+        let expected = vector(vec![1.0]);
+        assert!(matches!(compare(Approximation::Exact, &actual, &expected), Err(Error::ShapeMismatchError(_, _))));
+    }
+
+    #[test]
+    fn mismatch_reports_first_offending_index() {
+        let actual = vector(vec![1.0, 2.0, 5.0]);
+        let expected = vector(vec![1.0, 2.0, 3.0]);
+        match compare(Approximation::Exact, &actual, &expected) {
+            Err(Error::ValueMismatchError(index, a, b)) => {
+                assert_eq!(index, 2);
+                assert_eq!(a, 5.0);
+                assert_eq!(b, 3.0);
+            }
+            other => panic!("expected a value mismatch, got {other:?}"),
+        }
+    }
+}
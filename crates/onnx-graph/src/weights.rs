@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use crate::tensor::Tensor;
+use crate::Error;
+
+/// Where a graph's constant tensor data ends up when a `ModelProto` is built.
+/// Implementations back `WeightStorageStrategy` in `lib.rs`. Every manager
+/// that can currently be constructed embeds tensor data inline via
+/// `SingleOutputNode::get_initializer` (see `operators::Constant`); this
+/// trait exists so `build_proto` can stay agnostic of the storage strategy,
+/// and so an external-file-backed manager has somewhere to hang real
+/// byte-writing off of once one is implemented.
+pub trait WeightExternalOutputManager<'a> {
+    /// Called once all tensors have been gathered, before any `ModelProto`
+    /// fields are read. Implementations that buffer writes (e.g. to a `.bin`
+    /// file) flush here.
+    fn finalize_tensor_data(&mut self) {}
+}
+
+/// Embeds every tensor inline in the `ModelProto` as a `TensorProto`; nothing
+/// is written externally.
+pub struct NullOutputManager;
+
+impl NullOutputManager {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl<'a> WeightExternalOutputManager<'a> for NullOutputManager {}
+
+/// Embeds every tensor inline, same as `NullOutputManager`; kept distinct so
+/// callers can name the strategy they mean at the call site.
+pub struct EmbeddedOutputManager<'a> {
+    _marker: std::marker::PhantomData<&'a ()>,
+}
+
+impl<'a> EmbeddedOutputManager<'a> {
+    pub fn new() -> Self {
+        Self { _marker: std::marker::PhantomData }
+    }
+}
+
+impl<'a> WeightExternalOutputManager<'a> for EmbeddedOutputManager<'a> {}
+
+/// Resolves named weight tensors for a model-building helper (e.g.
+/// `pytorch::linear`), scoped under a dotted prefix the way PyTorch's
+/// `state_dict` keys are (`"layers.0.attn.weight"`).
+pub trait WeightManager: Sized {
+    fn get_tensor(&self, name: &str) -> Result<Arc<dyn Tensor>, Error>;
+
+    fn get_prefix(&self) -> Option<&str>;
+
+    /// Returns a `WeightManager` scoped to `"{current_prefix}.{name}"`.
+    fn prefix(&self, name: &str) -> Self;
+}